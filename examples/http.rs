@@ -1,5 +1,6 @@
 use pragma_rs::{
-    AggregationMode, Environment, GetEntryParams, InstrumentType, Interval, PragmaClient,
+    AggregationMode, Environment, FundingSource, GetEntryParams, InstrumentType, Interval,
+    PragmaClient, Timestamp,
 };
 
 #[tokio::main]
@@ -22,6 +23,7 @@ async fn main() {
                 aggregation: Some(AggregationMode::Median),
                 entry_type: Some(InstrumentType::Perp),
                 with_components: Some(false),
+                max_age: None,
             }),
         )
         .await
@@ -29,13 +31,19 @@ async fn main() {
     println!("BTC/USD data:\n{r:?}");
 
     let r = client
-        .get_historical_funding_rates("BTC", "USD", 1746448809, 1746535238, "hyperliquid")
+        .get_historical_funding_rates(
+            "BTC",
+            "USD",
+            Timestamp::from_secs(1746448809),
+            Timestamp::from_secs(1746535238),
+            &FundingSource::Hyperliquid,
+        )
         .await
         .unwrap();
     println!("BTC/USD historical funding rates:\n{r:?}");
 
     let r = client
-        .get_funding_rates("BTC", "USD", "hyperliquid", None)
+        .get_funding_rates("BTC", "USD", &FundingSource::Hyperliquid, None)
         .await
         .unwrap();
     println!("BTC/USD funding rates:\n{r:?}");
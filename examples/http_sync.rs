@@ -1,5 +1,6 @@
 use pragma_rs::{
-    AggregationMode, Environment, GetEntryParams, InstrumentType, Interval, PragmaClient,
+    AggregationMode, Environment, FundingSource, GetEntryParams, InstrumentType, Interval,
+    PragmaClient, Timestamp,
 };
 
 fn main() {
@@ -21,6 +22,7 @@ fn main() {
                 aggregation: Some(AggregationMode::Median),
                 entry_type: Some(InstrumentType::Perp),
                 with_components: Some(false),
+                max_age: None,
             }),
         )
         .unwrap();
@@ -28,12 +30,18 @@ fn main() {
     println!("BTC/USD data:\n{r:?}");
 
     let r = client
-        .get_historical_funding_rates_sync("BTC", "USD", 1746448809, 1746535238, "hyperliquid")
+        .get_historical_funding_rates_sync(
+            "BTC",
+            "USD",
+            Timestamp::from_secs(1746448809),
+            Timestamp::from_secs(1746535238),
+            &FundingSource::Hyperliquid,
+        )
         .unwrap();
     println!("BTC/USD historical funding rates:\n{r:?}");
 
     let r = client
-        .get_funding_rates_sync("BTC", "USD", "hyperliquid", None)
+        .get_funding_rates_sync("BTC", "USD", &FundingSource::Hyperliquid, None)
         .unwrap();
     println!("BTC/USD funding rates:\n{r:?}");
 }
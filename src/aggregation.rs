@@ -0,0 +1,321 @@
+use std::collections::{BTreeMap, HashMap};
+
+use pragma_common::aggregation::AggregationMode;
+
+use crate::{
+    components::parse_raw_price, Component, OnchainComponent, PragmaError, PriceUpdate, Timestamp,
+};
+
+/// A price published by a source at a given time, the input of an [`Aggregator`].
+///
+/// Prices are in the units of the API, i.e. not scaled by the decimals of the pair: aggregating
+/// the components of a response gives a price with the same decimals as the response.
+pub trait PriceSample {
+    fn source(&self) -> &str;
+    fn price(&self) -> Result<f64, PragmaError>;
+    fn timestamp(&self) -> Timestamp;
+}
+
+impl PriceSample for Component {
+    fn source(&self) -> &str {
+        &self.source
+    }
+
+    fn price(&self) -> Result<f64, PragmaError> {
+        parse_raw_price(&self.price)
+    }
+
+    fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+}
+
+impl PriceSample for OnchainComponent {
+    fn source(&self) -> &str {
+        &self.source
+    }
+
+    fn price(&self) -> Result<f64, PragmaError> {
+        parse_raw_price(&self.price)
+    }
+
+    fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+}
+
+/// A price from any origin, e.g. the successive Lightspeed updates of a pair.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    pub source: String,
+    pub price: f64,
+    pub timestamp: Timestamp,
+}
+
+impl Sample {
+    pub fn new(source: impl Into<String>, price: f64, timestamp: Timestamp) -> Self {
+        Self {
+            source: source.into(),
+            price,
+            timestamp,
+        }
+    }
+}
+
+impl PriceSample for Sample {
+    fn source(&self) -> &str {
+        &self.source
+    }
+
+    fn price(&self) -> Result<f64, PragmaError> {
+        Ok(self.price)
+    }
+
+    fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+}
+
+impl PriceUpdate {
+    /// Converts the update to a `Sample` published by its pair at `timestamp`, the timestamp of
+    /// the `LightspeedMessage::PriceUpdate` carrying it.
+    pub fn to_sample(&self, timestamp: Timestamp) -> Result<Sample, PragmaError> {
+        Ok(Sample::new(
+            self.pair_id.clone(),
+            parse_raw_price(&self.price)?,
+            timestamp,
+        ))
+    }
+}
+
+/// How an [`Aggregator`] combines prices.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AggregationMethod {
+    /// The weighted median, averaging the two middle prices when they split the weights in half.
+    #[default]
+    Median,
+    /// The weighted mean.
+    Mean,
+    /// The time-weighted average price of each source, weighting its prices by how long they were
+    /// its latest, then the weighted mean of these averages.
+    Twap,
+}
+
+impl From<AggregationMode> for AggregationMethod {
+    fn from(mode: AggregationMode) -> Self {
+        match mode {
+            AggregationMode::Median => Self::Median,
+            AggregationMode::Twap => Self::Twap,
+        }
+    }
+}
+
+/// Aggregates prices locally, to reproduce the price computed by Pragma from its components or to
+/// apply custom source weights.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use pragma_rs::{AggregationMethod, Aggregator, Sample, Timestamp};
+///
+/// let now = Timestamp::from_secs(1_700_000_000);
+/// let samples = [
+///     Sample::new("BINANCE", 100.0, now),
+///     Sample::new("OKX", 101.0, now),
+///     Sample::new("KRAKEN", 110.0, now),
+/// ];
+///
+/// let median = Aggregator::new(AggregationMethod::Median).aggregate(&samples).unwrap();
+/// assert_eq!(median, Some(101.0));
+///
+/// let mean = Aggregator::new(AggregationMethod::Mean)
+///     .weight("KRAKEN", 0.0)
+///     .aggregate(&samples)
+///     .unwrap();
+/// assert_eq!(mean, Some(100.5));
+///
+/// let ticks = [
+///     Sample::new("BTC/USD", 100.0, now),
+///     Sample::new("BTC/USD", 110.0, now + Duration::from_secs(30)),
+/// ];
+/// let twap = Aggregator::new(AggregationMethod::Twap)
+///     .twap_until(now + Duration::from_secs(60))
+///     .aggregate(&ticks)
+///     .unwrap();
+/// assert_eq!(twap, Some(105.0));
+///
+/// // Each source has its own TWAP, however often it publishes.
+/// let samples = [
+///     Sample::new("BINANCE", 100.0, now),
+///     Sample::new("OKX", 200.0, now + Duration::from_secs(1)),
+///     Sample::new("BINANCE", 100.0, now + Duration::from_secs(60)),
+/// ];
+/// let twap = Aggregator::new(AggregationMethod::Twap).aggregate(&samples).unwrap();
+/// assert_eq!(twap, Some(150.0));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Aggregator {
+    method: AggregationMethod,
+    weights: HashMap<String, f64>,
+    default_weight: f64,
+    twap_until: Option<Timestamp>,
+}
+
+impl Aggregator {
+    /// Creates an aggregator giving the same weight to every source.
+    pub fn new(method: impl Into<AggregationMethod>) -> Self {
+        Self {
+            method: method.into(),
+            weights: HashMap::new(),
+            default_weight: 1.0,
+            twap_until: None,
+        }
+    }
+
+    /// Sets the weight of a source, case-insensitive. A weight of `0` ignores the source.
+    pub fn weight(mut self, source: impl AsRef<str>, weight: f64) -> Self {
+        self.weights
+            .insert(source.as_ref().to_uppercase(), weight.max(0.0));
+        self
+    }
+
+    /// Sets the weight of the sources without a weight of their own, `1` by default.
+    pub fn default_weight(mut self, weight: f64) -> Self {
+        self.default_weight = weight.max(0.0);
+        self
+    }
+
+    /// Sets the end of the TWAP window, weighting the latest price of each source until then.
+    /// Defaults to the latest timestamp of the samples, across sources.
+    pub fn twap_until(mut self, until: Timestamp) -> Self {
+        self.twap_until = Some(until);
+        self
+    }
+
+    fn weight_of(&self, source: &str) -> f64 {
+        self.weights
+            .get(&source.to_uppercase())
+            .copied()
+            .unwrap_or(self.default_weight)
+    }
+
+    /// Aggregates `samples`, or returns `None` if none of them has a positive weight.
+    pub fn aggregate<S: PriceSample>(&self, samples: &[S]) -> Result<Option<f64>, PragmaError> {
+        Ok(match self.method {
+            AggregationMethod::Median => weighted_median(&mut self.points(samples)?),
+            AggregationMethod::Mean => weighted_mean(&self.points(samples)?),
+            AggregationMethod::Twap => self.twap(samples)?,
+        })
+    }
+
+    /// Returns the `(price, weight, timestamp)` of the samples with a positive weight.
+    fn points<'a, S: PriceSample + 'a>(
+        &self,
+        samples: impl IntoIterator<Item = &'a S>,
+    ) -> Result<Vec<(f64, f64, Timestamp)>, PragmaError> {
+        let mut points = Vec::new();
+        for sample in samples {
+            let weight = self.weight_of(sample.source());
+            if weight > 0.0 {
+                points.push((sample.price()?, weight, sample.timestamp()));
+            }
+        }
+        Ok(points)
+    }
+
+    fn twap<S: PriceSample>(&self, samples: &[S]) -> Result<Option<f64>, PragmaError> {
+        let mut by_source: BTreeMap<String, Vec<&S>> = BTreeMap::new();
+        for sample in samples {
+            by_source
+                .entry(sample.source().to_uppercase())
+                .or_default()
+                .push(sample);
+        }
+        let mut sources = Vec::with_capacity(by_source.len());
+        for samples in by_source.into_values() {
+            let points = self.points(samples)?;
+            if !points.is_empty() {
+                sources.push(points);
+            }
+        }
+
+        let Some(until) = self.twap_until.or_else(|| {
+            sources
+                .iter()
+                .flatten()
+                .map(|(_, _, timestamp)| *timestamp)
+                .max()
+        }) else {
+            return Ok(None);
+        };
+
+        let twaps: Vec<_> = sources
+            .into_iter()
+            .filter_map(|mut points| {
+                let weight = points[0].1;
+                let twap = time_weighted_mean(&mut points, until)?;
+                Some((twap, weight, until))
+            })
+            .collect();
+        Ok(weighted_mean(&twaps))
+    }
+}
+
+/// Averages the prices of a single source, weighting each one by how long it was the latest
+/// before `until`.
+fn time_weighted_mean(points: &mut [(f64, f64, Timestamp)], until: Timestamp) -> Option<f64> {
+    points.sort_by_key(|(_, _, timestamp)| *timestamp);
+    let durations: Vec<_> = points
+        .iter()
+        .enumerate()
+        .map(|(i, (price, _, timestamp))| {
+            let next = points.get(i + 1).map_or(until, |(_, _, next)| *next);
+            let duration = next.saturating_duration_since(*timestamp).as_secs_f64();
+            (*price, duration, *timestamp)
+        })
+        .collect();
+
+    // Prices published at the same instant (e.g. a single component of a response) have no
+    // duration: fall back to their mean.
+    weighted_mean(&durations).or_else(|| {
+        let prices: Vec<_> = points
+            .iter()
+            .map(|(price, _, t)| (*price, 1.0, *t))
+            .collect();
+        weighted_mean(&prices)
+    })
+}
+
+fn weighted_mean(points: &[(f64, f64, Timestamp)]) -> Option<f64> {
+    let total: f64 = points.iter().map(|(_, weight, _)| weight).sum();
+    (total > 0.0).then(|| {
+        points
+            .iter()
+            .map(|(price, weight, _)| price * weight)
+            .sum::<f64>()
+            / total
+    })
+}
+
+fn weighted_median(points: &mut [(f64, f64, Timestamp)]) -> Option<f64> {
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let total: f64 = points.iter().map(|(_, weight, _)| weight).sum();
+    if total <= 0.0 {
+        return None;
+    }
+
+    let half = total / 2.0;
+    let mut cumulated = 0.0;
+    for (i, (price, weight, _)) in points.iter().enumerate() {
+        cumulated += weight;
+        if cumulated > half {
+            return Some(*price);
+        }
+        if cumulated == half {
+            let next = points[i + 1..].iter().find(|(_, weight, _)| *weight > 0.0);
+            return Some(next.map_or(*price, |(next, _, _)| (price + next) / 2.0));
+        }
+    }
+    points.last().map(|(price, _, _)| *price)
+}
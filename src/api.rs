@@ -0,0 +1,95 @@
+use async_trait::async_trait;
+
+use crate::{
+    FundingSource, GetEntryParams, GetEntryResponse, GetFundingRatesResponse,
+    GetHistoricalFundingRatesResponse, GetOnchainEntryParams, GetOnchainEntryResponse,
+    PragmaClient, PragmaError, Timestamp,
+};
+
+/// The HTTP endpoints of the Pragma API, as an object-safe trait.
+///
+/// Services can depend on `Arc<dyn PragmaApi>` (or a generic `A: PragmaApi`) instead of
+/// [`PragmaClient`], and swap in a fake such as `MockPragmaClient` (feature `testing`) in tests.
+///
+/// See the inherent methods of [`PragmaClient`] for the documentation of each endpoint.
+#[async_trait]
+pub trait PragmaApi: Send + Sync {
+    async fn get_entry(
+        &self,
+        base: &str,
+        quote: &str,
+        params: Option<GetEntryParams>,
+    ) -> Result<GetEntryResponse, PragmaError>;
+
+    async fn get_onchain_entry(
+        &self,
+        base: &str,
+        quote: &str,
+        params: GetOnchainEntryParams,
+    ) -> Result<GetOnchainEntryResponse, PragmaError>;
+
+    async fn get_funding_rates(
+        &self,
+        base: &str,
+        quote: &str,
+        source: &FundingSource,
+        timestamp: Option<Timestamp>,
+    ) -> Result<GetFundingRatesResponse, PragmaError>;
+
+    async fn get_historical_funding_rates(
+        &self,
+        base: &str,
+        quote: &str,
+        from_ts: Timestamp,
+        to_ts: Timestamp,
+        source: &FundingSource,
+    ) -> Result<GetHistoricalFundingRatesResponse, PragmaError>;
+
+    async fn is_healthy(&self) -> bool;
+}
+
+#[async_trait]
+impl PragmaApi for PragmaClient {
+    async fn get_entry(
+        &self,
+        base: &str,
+        quote: &str,
+        params: Option<GetEntryParams>,
+    ) -> Result<GetEntryResponse, PragmaError> {
+        PragmaClient::get_entry(self, base, quote, params).await
+    }
+
+    async fn get_onchain_entry(
+        &self,
+        base: &str,
+        quote: &str,
+        params: GetOnchainEntryParams,
+    ) -> Result<GetOnchainEntryResponse, PragmaError> {
+        PragmaClient::get_onchain_entry(self, base, quote, params).await
+    }
+
+    async fn get_funding_rates(
+        &self,
+        base: &str,
+        quote: &str,
+        source: &FundingSource,
+        timestamp: Option<Timestamp>,
+    ) -> Result<GetFundingRatesResponse, PragmaError> {
+        PragmaClient::get_funding_rates(self, base, quote, source, timestamp).await
+    }
+
+    async fn get_historical_funding_rates(
+        &self,
+        base: &str,
+        quote: &str,
+        from_ts: Timestamp,
+        to_ts: Timestamp,
+        source: &FundingSource,
+    ) -> Result<GetHistoricalFundingRatesResponse, PragmaError> {
+        PragmaClient::get_historical_funding_rates(self, base, quote, from_ts, to_ts, source).await
+    }
+
+    async fn is_healthy(&self) -> bool {
+        PragmaClient::is_healthy(self).await
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    fmt,
+    sync::{Arc, RwLock},
+};
+
+use reqwest::header::HeaderValue;
+use zeroize::Zeroizing;
+
+use crate::PragmaError;
+
+/// A Pragma API key, wiped from memory when dropped and never printed.
+///
+/// # Examples
+///
+/// ```
+/// use pragma_rs::ApiKey;
+///
+/// let key = ApiKey::new("your_api_key");
+/// assert_eq!(format!("{key:?}"), "ApiKey(***)");
+/// assert_eq!(key.expose(), "your_api_key");
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct ApiKey(Zeroizing<String>);
+
+impl ApiKey {
+    pub fn new(key: impl Into<String>) -> Self {
+        Self(Zeroizing::new(key.into()))
+    }
+
+    /// Returns the key itself, e.g. to send it to the API.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the key as the value of the `x-api-key` header, flagged as sensitive.
+    pub(crate) fn header_value(&self) -> Result<HeaderValue, PragmaError> {
+        let mut value = HeaderValue::from_str(self.expose())?;
+        value.set_sensitive(true);
+        Ok(value)
+    }
+}
+
+impl fmt::Debug for ApiKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ApiKey(***)")
+    }
+}
+
+impl From<String> for ApiKey {
+    fn from(key: String) -> Self {
+        Self::new(key)
+    }
+}
+
+impl From<&str> for ApiKey {
+    fn from(key: &str) -> Self {
+        Self::new(key)
+    }
+}
+
+/// Source of the API key sent with every request, see [`crate::Config::with_api_key_provider`].
+///
+/// The key is read before each HTTP request and each WebSocket connection, so a provider can
+/// rotate it at runtime. An `ApiKey` is a provider of itself.
+pub trait ApiKeyProvider: Send + Sync {
+    fn api_key(&self) -> ApiKey;
+}
+
+impl ApiKeyProvider for ApiKey {
+    fn api_key(&self) -> ApiKey {
+        self.clone()
+    }
+}
+
+/// An API key which can be replaced at runtime. Clones share the same key.
+///
+/// # Examples
+///
+/// ```
+/// use pragma_rs::{ApiKeyProvider, Config, Environment, PragmaClient, RotatingApiKey};
+///
+/// let key = RotatingApiKey::new("first_api_key");
+/// let config = Config::new("unused".to_string(), Environment::Development)
+///     .with_api_key_provider(key.clone());
+/// let client = PragmaClient::new(config).unwrap();
+///
+/// // The next requests of `client` use the new key.
+/// key.rotate("second_api_key");
+/// assert_eq!(key.api_key().expose(), "second_api_key");
+/// ```
+#[derive(Clone)]
+pub struct RotatingApiKey(Arc<RwLock<ApiKey>>);
+
+impl RotatingApiKey {
+    pub fn new(key: impl Into<ApiKey>) -> Self {
+        Self(Arc::new(RwLock::new(key.into())))
+    }
+
+    /// Replaces the key.
+    pub fn rotate(&self, key: impl Into<ApiKey>) {
+        *self.0.write().unwrap_or_else(|e| e.into_inner()) = key.into();
+    }
+}
+
+impl fmt::Debug for RotatingApiKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RotatingApiKey(***)")
+    }
+}
+
+impl ApiKeyProvider for RotatingApiKey {
+    fn api_key(&self) -> ApiKey {
+        self.0.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// The API key provider of a `Config`, compared by identity and never printed.
+#[derive(Clone)]
+pub(crate) struct SharedApiKey(pub(crate) Arc<dyn ApiKeyProvider>);
+
+impl SharedApiKey {
+    pub(crate) fn new(provider: impl ApiKeyProvider + 'static) -> Self {
+        Self(Arc::new(provider))
+    }
+
+    pub(crate) fn get(&self) -> ApiKey {
+        self.0.api_key()
+    }
+}
+
+impl fmt::Debug for SharedApiKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("***")
+    }
+}
+
+impl PartialEq for SharedApiKey {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
@@ -0,0 +1,351 @@
+use std::{future::Future, sync::mpsc::RecvTimeoutError, sync::Arc, time::Duration};
+
+use tokio::runtime::{Builder, Handle, Runtime};
+
+use crate::{
+    Config, FundingSource, GetEntryParams, GetEntryResponse, GetFundingRatesAllSourcesResponse,
+    GetFundingRatesResponse, GetHistoricalFundingRatesResponse, GetOnchainEntryParams,
+    GetOnchainEntryResponse, Health, HistoryChunking, LightspeedMessage,
+    ListFundingRateInstrumentsResponse, PairMessage, PragmaClient, PragmaError, PragmaWsClient,
+    StarkexMessage, Timestamp, WsError,
+};
+
+/// How long dropping a `BlockingWsClient` waits for its close frame to be sent.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Runs `future` to completion on `runtime`, from synchronous code.
+///
+/// `Runtime::block_on` panics when called from an async context, e.g. from a blocking call nested
+/// in an async function. The future is then run from a scoped thread instead, and the calling
+/// thread is blocked until it completes: on a current-thread runtime, the other tasks of the
+/// caller are stalled meanwhile.
+pub(crate) fn block_on<F>(runtime: &Runtime, future: F) -> F::Output
+where
+    F: Future + Send,
+    F::Output: Send,
+{
+    if Handle::try_current().is_err() {
+        return runtime.block_on(future);
+    }
+    std::thread::scope(
+        |scope| match scope.spawn(|| runtime.block_on(future)).join() {
+            Ok(output) => output,
+            Err(panic) => std::panic::resume_unwind(panic),
+        },
+    )
+}
+
+/// A runtime which can be dropped from an async context.
+#[derive(Debug)]
+struct OwnedRuntime(Option<Runtime>);
+
+impl OwnedRuntime {
+    fn block_on<F>(&self, future: F) -> F::Output
+    where
+        F: Future + Send,
+        F::Output: Send,
+    {
+        match &self.0 {
+            Some(runtime) => block_on(runtime, future),
+            None => unreachable!("the runtime is only taken on drop"),
+        }
+    }
+}
+
+impl Drop for OwnedRuntime {
+    fn drop(&mut self) {
+        if let Some(runtime) = self.0.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+/// A blocking client for the HTTP endpoints of the Pragma API, for synchronous applications.
+///
+/// The client owns the runtime running its requests, so it can be used from any thread, with or
+/// without a tokio runtime. Calls made from an async context do not panic, but block the calling
+/// thread until they complete: async code should rather use [`PragmaClient`] directly, or wrap
+/// the calls in `tokio::task::spawn_blocking`.
+///
+/// Clones share the runtime and the underlying `PragmaClient`, whose HTTP connections are only
+/// used from that runtime.
+///
+/// # Examples
+///
+/// ```no_run
+/// use pragma_rs::{BlockingPragmaClient, Config, Environment};
+///
+/// fn main() -> Result<(), pragma_rs::PragmaError> {
+///     let config = Config::new("your_api_key".to_string(), Environment::Development);
+///     let client = BlockingPragmaClient::new(config)?;
+///     let response = client.get_entry("BTC", "USD", None)?;
+///     println!("Price: {}", response.price);
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct BlockingPragmaClient {
+    client: PragmaClient,
+    runtime: Arc<OwnedRuntime>,
+}
+
+impl BlockingPragmaClient {
+    /// Creates a new `BlockingPragmaClient` instance with the given configuration.
+    pub fn new(config: Config) -> Result<Self, PragmaError> {
+        Self::from_client(PragmaClient::new(config)?)
+    }
+
+    /// Wraps an existing `PragmaClient`, sharing its cache and rate limiter.
+    ///
+    /// Pooled HTTP connections are driven by the runtime which opened them, so the blocking client
+    /// uses its own connection pool: it keeps working after the runtime of `client` shuts down.
+    pub fn from_client(client: PragmaClient) -> Result<Self, PragmaError> {
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("pragma-blocking")
+            .enable_all()
+            .build()?;
+        let client = PragmaClient {
+            http_client: reqwest::Client::builder().build()?,
+            ..client
+        };
+        Ok(Self {
+            client,
+            runtime: Arc::new(OwnedRuntime(Some(runtime))),
+        })
+    }
+
+    /// Returns the async client used by this client.
+    pub fn client(&self) -> &PragmaClient {
+        &self.client
+    }
+
+    fn block_on<F>(&self, future: F) -> F::Output
+    where
+        F: Future + Send,
+        F::Output: Send,
+    {
+        self.runtime.block_on(future)
+    }
+
+    /// Creates a blocking WebSocket client for the Lightspeed endpoint, running on the runtime of
+    /// this client. See [`PragmaClient::lightspeed_ws_client`].
+    pub fn lightspeed_ws_client(&self) -> BlockingWsClient<LightspeedMessage> {
+        BlockingWsClient::new(self.client.lightspeed_ws_client(), self.runtime.clone())
+    }
+
+    /// Creates a blocking WebSocket client for the StarkEx endpoint, running on the runtime of
+    /// this client. See [`PragmaClient::starkex_ws_client`].
+    pub fn starkex_ws_client(&self) -> BlockingWsClient<StarkexMessage> {
+        BlockingWsClient::new(self.client.starkex_ws_client(), self.runtime.clone())
+    }
+
+    /// See [`PragmaClient::get_entry`].
+    pub fn get_entry(
+        &self,
+        base: &str,
+        quote: &str,
+        params: Option<GetEntryParams>,
+    ) -> Result<GetEntryResponse, PragmaError> {
+        self.block_on(self.client.get_entry(base, quote, params))
+    }
+
+    /// See [`PragmaClient::get_onchain_entry`].
+    pub fn get_onchain_entry(
+        &self,
+        base: &str,
+        quote: &str,
+        params: GetOnchainEntryParams,
+    ) -> Result<GetOnchainEntryResponse, PragmaError> {
+        self.block_on(self.client.get_onchain_entry(base, quote, params))
+    }
+
+    /// See [`PragmaClient::get_funding_rates`].
+    pub fn get_funding_rates(
+        &self,
+        base: &str,
+        quote: &str,
+        source: &FundingSource,
+        timestamp: Option<Timestamp>,
+    ) -> Result<GetFundingRatesResponse, PragmaError> {
+        self.block_on(
+            self.client
+                .get_funding_rates(base, quote, source, timestamp),
+        )
+    }
+
+    /// See [`PragmaClient::get_funding_rates_all_sources`].
+    pub fn get_funding_rates_all_sources(
+        &self,
+        base: &str,
+        quote: &str,
+        timestamp: Option<Timestamp>,
+    ) -> Result<GetFundingRatesAllSourcesResponse, PragmaError> {
+        self.block_on(
+            self.client
+                .get_funding_rates_all_sources(base, quote, timestamp),
+        )
+    }
+
+    /// See [`PragmaClient::get_historical_funding_rates`].
+    pub fn get_historical_funding_rates(
+        &self,
+        base: &str,
+        quote: &str,
+        from_ts: Timestamp,
+        to_ts: Timestamp,
+        source: &FundingSource,
+    ) -> Result<GetHistoricalFundingRatesResponse, PragmaError> {
+        self.block_on(
+            self.client
+                .get_historical_funding_rates(base, quote, from_ts, to_ts, source),
+        )
+    }
+
+    /// See [`PragmaClient::get_historical_funding_rates_chunked`].
+    pub fn get_historical_funding_rates_chunked(
+        &self,
+        base: &str,
+        quote: &str,
+        from_ts: Timestamp,
+        to_ts: Timestamp,
+        source: &FundingSource,
+        chunking: HistoryChunking,
+    ) -> Result<GetHistoricalFundingRatesResponse, PragmaError> {
+        self.block_on(
+            self.client.get_historical_funding_rates_chunked(
+                base, quote, from_ts, to_ts, source, chunking,
+            ),
+        )
+    }
+
+    /// See [`PragmaClient::list_funding_rate_sources`].
+    pub fn list_funding_rate_sources(&self) -> Result<Vec<FundingSource>, PragmaError> {
+        self.block_on(self.client.list_funding_rate_sources())
+    }
+
+    /// See [`PragmaClient::list_funding_rate_instruments`].
+    pub fn list_funding_rate_instruments(
+        &self,
+        source: &FundingSource,
+    ) -> Result<ListFundingRateInstrumentsResponse, PragmaError> {
+        self.block_on(self.client.list_funding_rate_instruments(source))
+    }
+
+    /// See [`PragmaClient::is_healthy`].
+    pub fn is_healthy(&self) -> bool {
+        self.block_on(self.client.is_healthy())
+    }
+
+    /// See [`PragmaClient::health`].
+    pub fn health(&self) -> Health {
+        self.block_on(self.client.health())
+    }
+}
+
+/// A blocking WebSocket client, created by [`BlockingPragmaClient::lightspeed_ws_client`] or
+/// [`BlockingPragmaClient::starkex_ws_client`].
+///
+/// Iterating over the client yields the received messages until the connection is closed.
+/// Dropping the client closes the connection.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::{sync::mpsc::RecvTimeoutError, time::Duration};
+/// use pragma_rs::{BlockingPragmaClient, Config, Environment};
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let config = Config::new("your_api_key".to_string(), Environment::Development);
+///     let client = BlockingPragmaClient::new(config)?;
+///     let mut ws_client = client.lightspeed_ws_client();
+///     ws_client.connect()?;
+///     ws_client.subscribe(vec!["BTC/USD".to_string()])?;
+///
+///     loop {
+///         match ws_client.recv_timeout(Duration::from_secs(5)) {
+///             Ok(msg) => println!("{msg:?}"),
+///             Err(RecvTimeoutError::Timeout) => println!("no update for 5 seconds"),
+///             Err(RecvTimeoutError::Disconnected) => break,
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+pub struct BlockingWsClient<T: PairMessage> {
+    /// Only `None` while the client is dropped.
+    client: Option<PragmaWsClient<T>>,
+    runtime: Arc<OwnedRuntime>,
+}
+
+impl<T: PairMessage> Drop for BlockingWsClient<T> {
+    fn drop(&mut self) {
+        if let Some(client) = self.client.take() {
+            self.runtime
+                .block_on(async { tokio::time::timeout(CLOSE_TIMEOUT, client.close()).await })
+                .ok();
+        }
+    }
+}
+
+impl<T: PairMessage> BlockingWsClient<T> {
+    fn new(client: PragmaWsClient<T>, runtime: Arc<OwnedRuntime>) -> Self {
+        Self {
+            client: Some(client),
+            runtime,
+        }
+    }
+
+    fn client(&mut self) -> &mut PragmaWsClient<T> {
+        match &mut self.client {
+            Some(client) => client,
+            None => unreachable!("the client is only taken on drop"),
+        }
+    }
+
+    /// Connects to the WebSocket. See [`PragmaWsClient::connect`].
+    pub fn connect(&mut self) -> Result<(), WsError> {
+        let runtime = self.runtime.clone();
+        runtime.block_on(self.client().connect())
+    }
+
+    /// Sends a message to the WebSocket.
+    pub fn send(&mut self, msg: T) -> Result<(), WsError> {
+        self.client().send(msg)
+    }
+
+    /// Subscribes to the updates of `pairs`.
+    pub fn subscribe(&mut self, pairs: Vec<String>) -> Result<(), WsError> {
+        self.send(T::subscribe(pairs))
+    }
+
+    /// Unsubscribes from the updates of `pairs`.
+    pub fn unsubscribe(&mut self, pairs: Vec<String>) -> Result<(), WsError> {
+        self.send(T::unsubscribe(pairs))
+    }
+
+    /// Blocks until the next message is received, or returns `None` once the connection is closed.
+    pub fn recv(&mut self) -> Option<T> {
+        let runtime = self.runtime.clone();
+        runtime.block_on(self.client().recv())
+    }
+
+    /// Blocks until the next message is received, for at most `timeout`.
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        let runtime = self.runtime.clone();
+        let client = self.client();
+        match runtime.block_on(async { tokio::time::timeout(timeout, client.recv()).await }) {
+            Ok(Some(msg)) => Ok(msg),
+            Ok(None) => Err(RecvTimeoutError::Disconnected),
+            Err(_) => Err(RecvTimeoutError::Timeout),
+        }
+    }
+}
+
+impl<T: PairMessage> Iterator for BlockingWsClient<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.recv()
+    }
+}
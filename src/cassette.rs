@@ -0,0 +1,199 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    sync::{mpsc, Arc, Mutex},
+    thread::JoinHandle,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{PragmaError, Timestamp};
+
+/// A single event of a cassette, stored as one JSON line.
+///
+/// URLs are stored without their scheme and host (e.g. `/node/v1/data/BTC/USD?interval=1min`),
+/// so a cassette recorded against one environment can be replayed anywhere.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CassetteEntry {
+    /// An HTTP request and the response received.
+    Http {
+        at: Timestamp,
+        method: String,
+        url: String,
+        status: u16,
+        body: String,
+    },
+    /// A text frame sent to a WebSocket endpoint.
+    WsSent {
+        at: Timestamp,
+        url: String,
+        frame: String,
+    },
+    /// A text frame received from a WebSocket endpoint.
+    WsReceived {
+        at: Timestamp,
+        url: String,
+        frame: String,
+    },
+}
+
+/// How recorded WebSocket frames are replayed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayTiming {
+    /// Frames are delivered as fast as possible.
+    #[default]
+    Immediate,
+    /// Frames are delivered with the delays observed while recording.
+    Original,
+}
+
+/// Reads every entry of the cassette at `path`.
+pub fn read_cassette(path: impl AsRef<Path>) -> Result<Vec<CassetteEntry>, PragmaError> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut entries = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            entries.push(serde_json::from_str(&line)?);
+        }
+    }
+    Ok(entries)
+}
+
+/// Returns the path and query of `url`, used to identify requests in a cassette.
+pub(crate) fn cassette_url(url: &reqwest::Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    }
+}
+
+/// Appends the traffic of a client to a cassette. Clones write to the same file.
+///
+/// Entries are written by a dedicated thread, so recording never blocks the runtime of the client.
+#[derive(Debug, Clone)]
+pub(crate) struct Recorder {
+    writer: Arc<Writer>,
+}
+
+/// The thread writing the lines of a cassette. Dropping it waits for the pending lines.
+#[derive(Debug)]
+struct Writer {
+    lines: Option<mpsc::Sender<String>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for Writer {
+    fn drop(&mut self) {
+        // Closing the channel stops the thread once it wrote and flushed the pending lines.
+        self.lines.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Writes `lines` to `file`, flushing whenever no line is pending.
+fn write_lines(file: File, lines: mpsc::Receiver<String>) {
+    let mut file = BufWriter::new(file);
+    while let Ok(line) = lines.recv() {
+        let _ = file.write_all(line.as_bytes());
+        while let Ok(line) = lines.try_recv() {
+            let _ = file.write_all(line.as_bytes());
+        }
+        let _ = file.flush();
+    }
+}
+
+impl Recorder {
+    /// Creates the cassette at `path`, truncating it if it exists.
+    pub(crate) fn create(path: &Path) -> Result<Self, PragmaError> {
+        let file = File::create(path)?;
+        let (lines, receiver) = mpsc::channel();
+        let thread = std::thread::Builder::new()
+            .name("pragma-cassette".to_string())
+            .spawn(move || write_lines(file, receiver))?;
+        Ok(Self {
+            writer: Arc::new(Writer {
+                lines: Some(lines),
+                thread: Some(thread),
+            }),
+        })
+    }
+
+    /// Queues `entry` to be written as a new line. Failures are ignored: recording never breaks
+    /// the client.
+    pub(crate) fn record(&self, entry: &CassetteEntry) {
+        if let (Ok(mut line), Some(lines)) = (serde_json::to_string(entry), &self.writer.lines) {
+            line.push('\n');
+            let _ = lines.send(line);
+        }
+    }
+}
+
+/// Recorded `(status, body)` responses, keyed by `(method, url)`.
+type HttpResponses = HashMap<(String, String), VecDeque<(u16, String)>>;
+
+/// Serves the traffic stored in a cassette.
+#[derive(Debug)]
+pub(crate) struct Replayer {
+    /// Recorded responses for each `(method, url)`, consumed in order. The last one is kept to
+    /// answer any further identical request.
+    http: Mutex<HttpResponses>,
+    /// Received frames for each WebSocket url, with their recording time.
+    ws: HashMap<String, Vec<(Timestamp, String)>>,
+    pub(crate) timing: ReplayTiming,
+}
+
+impl Replayer {
+    pub(crate) fn load(path: &Path, timing: ReplayTiming) -> Result<Self, PragmaError> {
+        let mut http = HttpResponses::new();
+        let mut ws: HashMap<_, Vec<_>> = HashMap::new();
+        for entry in read_cassette(path)? {
+            match entry {
+                CassetteEntry::Http {
+                    method,
+                    url,
+                    status,
+                    body,
+                    ..
+                } => http
+                    .entry((method, url))
+                    .or_default()
+                    .push_back((status, body)),
+                CassetteEntry::WsReceived { at, url, frame } => {
+                    ws.entry(url).or_default().push((at, frame));
+                }
+                CassetteEntry::WsSent { .. } => {}
+            }
+        }
+        Ok(Self {
+            http: Mutex::new(http),
+            ws,
+            timing,
+        })
+    }
+
+    /// Returns the recorded status and body for a request.
+    pub(crate) fn http(&self, method: &str, url: &str) -> Result<(u16, String), PragmaError> {
+        let mut http = self.http.lock().unwrap_or_else(|e| e.into_inner());
+        let responses = http
+            .get_mut(&(method.to_string(), url.to_string()))
+            .filter(|responses| !responses.is_empty())
+            .ok_or_else(|| PragmaError::CassetteMiss(format!("{method} {url}")))?;
+        let response = if responses.len() > 1 {
+            responses.pop_front()
+        } else {
+            responses.front().cloned()
+        };
+        response.ok_or_else(|| PragmaError::CassetteMiss(format!("{method} {url}")))
+    }
+
+    /// Returns the recorded frames received on a WebSocket url.
+    pub(crate) fn ws_frames(&self, url: &str) -> Vec<(Timestamp, String)> {
+        self.ws.get(url).cloned().unwrap_or_default()
+    }
+}
@@ -1,19 +1,23 @@
+use std::sync::Arc;
 #[cfg(feature = "sync")]
-use std::sync::Once;
+use std::{future::Future, sync::OnceLock};
 
-use reqwest::{
-    header::{HeaderMap, HeaderValue},
-    Client,
-};
+use reqwest::Client;
 #[cfg(feature = "sync")]
 use tokio::runtime::Runtime;
 
-use crate::{Config, PragmaError};
-
 #[cfg(feature = "sync")]
-static INIT: Once = Once::new();
+use crate::blocking;
+use crate::{
+    cassette::{Recorder, Replayer},
+    failover::Endpoints,
+    http::offchain::entry::cache::EntryCache,
+    rate_limit::RateLimiter,
+    Config, PragmaError,
+};
+
 #[cfg(feature = "sync")]
-static mut RUNTIME: Option<Runtime> = None;
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
 
 /// HTTP client for interacting with Pragma API offchain and onchain endpoints.
 ///
@@ -33,33 +37,61 @@ static mut RUNTIME: Option<Runtime> = None;
 pub struct PragmaClient {
     pub(crate) config: Config,
     pub(crate) http_client: reqwest::Client,
+    pub(crate) recorder: Option<Recorder>,
+    pub(crate) replayer: Option<Arc<Replayer>>,
+    pub(crate) entry_cache: Option<Arc<EntryCache>>,
+    pub(crate) rate_limiter: Option<Arc<RateLimiter>>,
+    pub(crate) endpoints: Arc<Endpoints>,
 }
 
 impl PragmaClient {
     /// Creates a new `PragmaClient` instance with the given configuration.
     pub fn new(config: Config) -> Result<Self, PragmaError> {
-        let mut headers = HeaderMap::new();
-        headers.insert("x-api-key", HeaderValue::from_str(&config.api_key)?);
+        config.validate()?;
+        // The key is sent with each request, so that its provider can rotate it.
+        config.api_key.get().header_value()?;
+        let http_client = Client::builder().build()?;
+
+        let recorder = match &config.record_to {
+            Some(path) => Some(Recorder::create(path)?),
+            None => None,
+        };
+        let replayer = match &config.replay {
+            Some((cassette, timing)) => Some(Arc::new(Replayer::load(cassette, *timing)?)),
+            None => None,
+        };
+
+        let entry_cache = config.entry_cache.map(|c| Arc::new(EntryCache::new(c)));
+        let rate_limiter = config
+            .rate_limit
+            .as_ref()
+            .map(|c| Arc::new(RateLimiter::new(c)));
 
-        let http_client = Client::builder().default_headers(headers.clone()).build()?;
+        let endpoints = Arc::new(Endpoints::new(&config));
 
         Ok(Self {
             config,
             http_client,
+            recorder,
+            replayer,
+            entry_cache,
+            rate_limiter,
+            endpoints,
         })
     }
 
-    /// Gets or creates the thread-local blocking client.
+    /// Runs `future` to completion on the runtime shared by the `*_sync` methods, which is created
+    /// on first use. Can be called from an async context, see [`crate::blocking::block_on`].
     #[cfg(feature = "sync")]
-    pub(crate) fn runtime() -> &'static Runtime {
-        unsafe {
-            INIT.call_once(|| {
-                RUNTIME = match Runtime::new() {
-                    Ok(rt) => Some(rt),
-                    Err(e) => panic!("Failed to initialize runtime: {e}"),
-                };
-            });
-            RUNTIME.as_ref().unwrap()
-        }
+    pub(crate) fn block_on<F>(future: F) -> F::Output
+    where
+        F: Future + Send,
+        F::Output: Send,
+    {
+        let runtime = RUNTIME.get_or_init(|| match Runtime::new() {
+            Ok(rt) => rt,
+            Err(e) => panic!("Failed to initialize runtime: {e}"),
+        });
+        blocking::block_on(runtime, future)
     }
 }
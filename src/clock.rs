@@ -0,0 +1,97 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::{PragmaError, Timestamp};
+
+/// Source of the current time, used to compute the age of prices.
+///
+/// The clients use [`SystemClock`] unless another clock is set with [`crate::Config::with_clock`].
+pub trait Clock: fmt::Debug + Send + Sync {
+    fn now(&self) -> Timestamp;
+}
+
+/// The system time.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Timestamp {
+        Timestamp::now()
+    }
+}
+
+/// A clock only moving when told to, for tests. Clones share the same time.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use pragma_rs::{Clock, ManualClock, Timestamp};
+///
+/// let clock = ManualClock::new(Timestamp::from_secs(1_700_000_000));
+/// clock.advance(Duration::from_secs(30));
+/// assert_eq!(clock.now(), Timestamp::from_secs(1_700_000_030));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ManualClock(Arc<AtomicU64>);
+
+impl ManualClock {
+    pub fn new(now: Timestamp) -> Self {
+        Self(Arc::new(AtomicU64::new(now.as_millis())))
+    }
+
+    pub fn set(&self, now: Timestamp) {
+        self.0.store(now.as_millis(), Ordering::SeqCst);
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.0
+            .fetch_add(duration.as_millis() as u64, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Timestamp {
+        Timestamp::from_millis(self.0.load(Ordering::SeqCst))
+    }
+}
+
+/// The clock of a `Config`, compared by identity.
+#[derive(Debug, Clone)]
+pub(crate) struct SharedClock(pub(crate) Arc<dyn Clock>);
+
+impl Default for SharedClock {
+    fn default() -> Self {
+        Self(Arc::new(SystemClock))
+    }
+}
+
+impl PartialEq for SharedClock {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl SharedClock {
+    /// Fails with `StalePrice` if a price published at `timestamp` is older than `max_age`.
+    pub(crate) fn check_freshness(
+        &self,
+        timestamp: Timestamp,
+        max_age: Option<Duration>,
+    ) -> Result<(), PragmaError> {
+        let Some(max_age) = max_age else {
+            return Ok(());
+        };
+        let age = self.0.now().saturating_duration_since(timestamp);
+        if age > max_age {
+            return Err(PragmaError::StalePrice { age, max_age });
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,379 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    AggregationMethod, Aggregator, Component, GetEntryResponse, GetOnchainEntryResponse,
+    OnchainComponent, PragmaError, PriceSample, Timestamp,
+};
+
+/// Scale factor making the median absolute deviation a consistent estimator of the standard
+/// deviation for normally distributed prices.
+const MAD_SCALE: f64 = 1.4826;
+
+/// How to flag the components whose price is an outlier.
+///
+/// # Examples
+///
+/// ```
+/// use pragma_rs::{Component, GetEntryResponse, OutlierMethod, Timestamp};
+///
+/// let component = |source: &str, price: u128| Component {
+///     price: format!("{price:#x}"),
+///     source: source.to_string(),
+///     timestamp: Timestamp::from_secs(1_700_000_000),
+/// };
+/// let response = GetEntryResponse {
+///     decimals: 2,
+///     num_sources_aggregated: 4,
+///     pair_id: "BTC/USD".to_string(),
+///     price: format!("{:#x}", 10_000),
+///     timestamp: Timestamp::from_secs(1_700_000_000),
+///     components: Some(vec![
+///         component("BINANCE", 9_990),
+///         component("OKX", 10_000),
+///         component("BYBIT", 10_010),
+///         component("KRAKEN", 12_000),
+///     ]),
+/// };
+///
+/// let outliers = response.outliers(OutlierMethod::PercentBand { percent: 5.0 }).unwrap();
+/// assert_eq!(outliers.len(), 1);
+/// assert_eq!(outliers[0].source, "KRAKEN");
+/// assert_eq!(response.local_median().unwrap(), Some(100.05));
+/// assert_eq!(response.local_median_raw().unwrap(), Some(10_005));
+/// assert_eq!(response.num_sources_matches(), Some(true));
+///
+/// // With 18 decimals, prices exceed the precision of an `f64`: the `_raw` methods are exact.
+/// let precise = GetEntryResponse {
+///     decimals: 18,
+///     price: format!("{:#x}", 100_000_000_000_000_000_001u128),
+///     components: Some(vec![
+///         component("BINANCE", 100_000_000_000_000_000_001),
+///         component("OKX", 100_000_000_000_000_000_001),
+///     ]),
+///     ..response
+/// };
+/// assert_eq!(precise.local_mean_raw().unwrap(), Some(100_000_000_000_000_000_001));
+///
+/// // Sources differing only by case are a single source, as in the `Aggregator`.
+/// let mixed_case = GetEntryResponse {
+///     num_sources_aggregated: 1,
+///     components: Some(vec![component("Binance", 9_990), component("BINANCE", 10_010)]),
+///     ..precise
+/// };
+/// assert_eq!(mixed_case.num_sources_matches(), Some(true));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutlierMethod {
+    /// Flags the prices further than `threshold` scaled median absolute deviations from the median.
+    Mad { threshold: f64 },
+
+    /// Flags the prices further than `threshold` standard deviations from the mean.
+    ZScore { threshold: f64 },
+
+    /// Flags the prices deviating from the aggregated price by more than `percent`.
+    PercentBand { percent: f64 },
+}
+
+/// The price of a component compared to the aggregated price.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentDeviation {
+    /// The source of the price.
+    pub source: String,
+
+    /// The price of the component, scaled by the decimals of the response.
+    pub price: f64,
+
+    /// The timestamp of the price.
+    pub timestamp: Timestamp,
+
+    /// The signed difference with the aggregated price, in basis points of the aggregated price.
+    pub deviation_bps: f64,
+}
+
+/// Parses a hexadecimal price exactly, without scaling it.
+fn parse_raw_u128(price: &str) -> Result<u128, PragmaError> {
+    u128::from_str_radix(price.trim_start_matches("0x"), 16)
+        .map_err(|e| PragmaError::InvalidPrice(format!("{price}: {e}")))
+}
+
+/// Parses a hexadecimal price, without scaling it.
+pub(crate) fn parse_raw_price(price: &str) -> Result<f64, PragmaError> {
+    Ok(parse_raw_u128(price)? as f64)
+}
+
+fn scale(decimals: u32) -> f64 {
+    10f64.powi(decimals as i32)
+}
+
+fn median(values: &[f64]) -> Option<f64> {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let mid = sorted.len() / 2;
+    match sorted.len() {
+        0 => None,
+        n if n % 2 == 0 => Some((sorted[mid - 1] + sorted[mid]) / 2.0),
+        _ => Some(sorted[mid]),
+    }
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// The mean of `values`, rounded down, summing quotients and remainders apart to never overflow.
+fn mean_u128(values: &[u128]) -> Option<u128> {
+    let n = values.len() as u128;
+    if n == 0 {
+        return None;
+    }
+    let quotients: u128 = values.iter().map(|v| v / n).sum();
+    let remainders: u128 = values.iter().map(|v| v % n).sum();
+    Some(quotients + remainders / n)
+}
+
+/// The median of `values`, rounding down the mean of the two middle values.
+fn median_u128(values: &mut [u128]) -> Option<u128> {
+    values.sort_unstable();
+    let mid = values.len() / 2;
+    match values.len() {
+        0 => None,
+        n if n % 2 == 0 => mean_u128(&values[mid - 1..=mid]),
+        _ => Some(values[mid]),
+    }
+}
+
+/// The prices of one source weighted by how many milliseconds they were the latest before
+/// `until`, rounded down, or their mean if they have no duration.
+fn twap_u128(points: &mut [(u128, Timestamp)], until: Timestamp) -> Result<u128, PragmaError> {
+    let overflow = || PragmaError::InvalidPrice("the TWAP overflows a u128".to_string());
+    points.sort_by_key(|(_, timestamp)| *timestamp);
+
+    let mut weighted = 0u128;
+    let mut total = 0u128;
+    for (i, (price, timestamp)) in points.iter().enumerate() {
+        let next = points.get(i + 1).map_or(until, |(_, next)| *next);
+        let duration = next.saturating_duration_since(*timestamp).as_millis();
+        weighted = price
+            .checked_mul(duration)
+            .and_then(|w| weighted.checked_add(w))
+            .ok_or_else(overflow)?;
+        total += duration;
+    }
+    if total == 0 {
+        let prices: Vec<u128> = points.iter().map(|(price, _)| *price).collect();
+        return mean_u128(&prices).ok_or_else(overflow);
+    }
+    Ok(weighted / total)
+}
+
+/// A component whose price is a hexadecimal string, so that it can be parsed exactly.
+trait RawComponent: PriceSample {
+    fn raw_price(&self) -> &str;
+}
+
+impl RawComponent for Component {
+    fn raw_price(&self) -> &str {
+        &self.price
+    }
+}
+
+impl RawComponent for OnchainComponent {
+    fn raw_price(&self) -> &str {
+        &self.price
+    }
+}
+
+/// Returns the distance from which a price is an outlier: a price `p` is flagged if
+/// `|p - center| > distance`. `None` if no price can be flagged.
+fn outlier_bounds(prices: &[f64], aggregate: f64, method: OutlierMethod) -> Option<(f64, f64)> {
+    let (center, scale, threshold) = match method {
+        OutlierMethod::Mad { threshold } => {
+            let center = median(prices)?;
+            let deviations: Vec<f64> = prices.iter().map(|p| (p - center).abs()).collect();
+            (center, median(&deviations)? * MAD_SCALE, threshold)
+        }
+        OutlierMethod::ZScore { threshold } => {
+            let center = mean(prices)?;
+            let variance =
+                prices.iter().map(|p| (p - center).powi(2)).sum::<f64>() / prices.len() as f64;
+            (center, variance.sqrt(), threshold)
+        }
+        OutlierMethod::PercentBand { percent } => (aggregate, aggregate.abs() / 100.0, percent),
+    };
+    (scale > 0.0).then_some((center, scale * threshold))
+}
+
+/// Analysis of the components of a response, shared by the offchain and onchain responses.
+struct Components<'a, C> {
+    components: &'a [C],
+    decimals: u32,
+    price: &'a str,
+    timestamp: Timestamp,
+}
+
+impl<C: RawComponent> Components<'_, C> {
+    fn deviations(&self) -> Result<Vec<ComponentDeviation>, PragmaError> {
+        let aggregate = parse_raw_price(self.price)? / scale(self.decimals);
+        self.components
+            .iter()
+            .map(|c| {
+                let price = c.price()? / scale(self.decimals);
+                let deviation_bps = if aggregate == 0.0 {
+                    0.0
+                } else {
+                    (price - aggregate) / aggregate * 10_000.0
+                };
+                Ok(ComponentDeviation {
+                    source: c.source().to_string(),
+                    price,
+                    timestamp: c.timestamp(),
+                    deviation_bps,
+                })
+            })
+            .collect()
+    }
+
+    fn outliers(&self, method: OutlierMethod) -> Result<Vec<ComponentDeviation>, PragmaError> {
+        let aggregate = parse_raw_price(self.price)? / scale(self.decimals);
+        let deviations = self.deviations()?;
+        let prices: Vec<f64> = deviations.iter().map(|d| d.price).collect();
+        let Some((center, distance)) = outlier_bounds(&prices, aggregate, method) else {
+            return Ok(Vec::new());
+        };
+        Ok(deviations
+            .into_iter()
+            .filter(|d| (d.price - center).abs() > distance)
+            .collect())
+    }
+
+    fn aggregate(&self, method: AggregationMethod) -> Result<Option<f64>, PragmaError> {
+        let aggregate = Aggregator::new(method)
+            .twap_until(self.timestamp)
+            .aggregate(self.components)?;
+        Ok(aggregate.map(|price| price / scale(self.decimals)))
+    }
+
+    /// Same as [`Self::aggregate`] on the unscaled integer prices.
+    fn aggregate_raw(&self, method: AggregationMethod) -> Result<Option<u128>, PragmaError> {
+        let mut by_source: BTreeMap<String, Vec<(u128, Timestamp)>> = BTreeMap::new();
+        for c in self.components {
+            by_source
+                .entry(c.source().to_uppercase())
+                .or_default()
+                .push((parse_raw_u128(c.raw_price())?, c.timestamp()));
+        }
+        let mut prices: Vec<u128> = by_source.values().flatten().map(|(p, _)| *p).collect();
+
+        Ok(match method {
+            AggregationMethod::Median => median_u128(&mut prices),
+            AggregationMethod::Mean => mean_u128(&prices),
+            AggregationMethod::Twap => {
+                let mut twaps = Vec::with_capacity(by_source.len());
+                for points in by_source.values_mut() {
+                    twaps.push(twap_u128(points, self.timestamp)?);
+                }
+                mean_u128(&twaps)
+            }
+        })
+    }
+
+    /// Counts the distinct sources, ignoring case like the [`Aggregator`] does.
+    fn num_sources(&self) -> usize {
+        let mut sources: Vec<String> = self
+            .components
+            .iter()
+            .map(|c| c.source().to_uppercase())
+            .collect();
+        sources.sort_unstable();
+        sources.dedup();
+        sources.len()
+    }
+}
+
+/// Defines the component analysis methods of a response type.
+macro_rules! impl_component_analysis {
+    ($response:ty, $timestamp:ident, $num_sources:ident) => {
+        impl $response {
+            fn analysis(&self) -> Components<'_, impl RawComponent> {
+                Components {
+                    components: self.components.as_deref().unwrap_or_default(),
+                    decimals: self.decimals,
+                    price: &self.price,
+                    timestamp: self.$timestamp,
+                }
+            }
+
+            /// Compares the price of each component to the aggregated price.
+            ///
+            /// Returns an empty list if the response has no components.
+            pub fn component_deviations(&self) -> Result<Vec<ComponentDeviation>, PragmaError> {
+                self.analysis().deviations()
+            }
+
+            /// Returns the components whose price is an outlier according to `method`.
+            pub fn outliers(
+                &self,
+                method: OutlierMethod,
+            ) -> Result<Vec<ComponentDeviation>, PragmaError> {
+                self.analysis().outliers(method)
+            }
+
+            /// Recomputes the median of the component prices, or `None` without components.
+            ///
+            /// The prices are converted to `f64`, which is exact up to 2^53: prices with 18
+            /// decimals lose their last digits. Compare [`Self::local_median_raw`] to the price of
+            /// the response instead when auditing it.
+            pub fn local_median(&self) -> Result<Option<f64>, PragmaError> {
+                self.analysis().aggregate(AggregationMethod::Median)
+            }
+
+            /// Recomputes the mean of the component prices, or `None` without components.
+            /// See [`Self::local_mean_raw`] for an exact result.
+            pub fn local_mean(&self) -> Result<Option<f64>, PragmaError> {
+                self.analysis().aggregate(AggregationMethod::Mean)
+            }
+
+            /// Recomputes the time-weighted average of the prices of each source, each price
+            /// being weighted by how long it was the latest before the timestamp of the response,
+            /// then the mean of these averages. Returns `None` without components.
+            /// See [`Self::local_twap_raw`] for an exact result.
+            pub fn local_twap(&self) -> Result<Option<f64>, PragmaError> {
+                self.analysis().aggregate(AggregationMethod::Twap)
+            }
+
+            /// Recomputes the median of the component prices on integers, with the decimals of
+            /// the response, or `None` without components. The mean of the two middle prices is
+            /// rounded down.
+            pub fn local_median_raw(&self) -> Result<Option<u128>, PragmaError> {
+                self.analysis().aggregate_raw(AggregationMethod::Median)
+            }
+
+            /// Recomputes the mean of the component prices on integers, rounded down, or `None`
+            /// without components.
+            pub fn local_mean_raw(&self) -> Result<Option<u128>, PragmaError> {
+                self.analysis().aggregate_raw(AggregationMethod::Mean)
+            }
+
+            /// Recomputes [`Self::local_twap`] on integers, weighting the prices by milliseconds
+            /// and rounding down, or `None` without components.
+            pub fn local_twap_raw(&self) -> Result<Option<u128>, PragmaError> {
+                self.analysis().aggregate_raw(AggregationMethod::Twap)
+            }
+
+            /// Checks that the number of sources aggregated matches the distinct sources of the
+            /// components, compared case-insensitively, or returns `None` if the response has no
+            /// components.
+            pub fn num_sources_matches(&self) -> Option<bool> {
+                self.components.as_ref()?;
+                Some(self.analysis().num_sources() == self.$num_sources as usize)
+            }
+        }
+    };
+}
+
+impl_component_analysis!(GetEntryResponse, timestamp, num_sources_aggregated);
+impl_component_analysis!(
+    GetOnchainEntryResponse,
+    last_updated_timestamp,
+    nb_sources_aggregated
+);
@@ -1,4 +1,64 @@
+use std::{path::PathBuf, str::FromStr, sync::Arc, time::Duration};
+
+use serde::Deserialize;
+
+use crate::{
+    api_key::SharedApiKey, cassette::ReplayTiming, clock::SharedClock, failover::Endpoint, ApiKey,
+    ApiKeyProvider, Clock, EntryCacheConfig, FailoverConfig, PragmaError, RateLimitConfig,
+    ReconnectConfig, DEFAULT_HEALTH_TIMEOUT,
+};
+
+const ENV_API_KEY: &str = "PRAGMA_API_KEY";
+const ENV_ENVIRONMENT: &str = "PRAGMA_ENVIRONMENT";
+const ENV_HTTP_URL: &str = "PRAGMA_HTTP_URL";
+const ENV_WS_URL: &str = "PRAGMA_WS_URL";
+
+const HTTP_SCHEMES: &[&str] = &["http", "https"];
+const WS_SCHEMES: &[&str] = &["ws", "wss"];
+
+/// Parses `url`, checks that its scheme is one of `schemes`, and returns it normalized (e.g. with
+/// a lowercase scheme and host) without trailing slash.
+fn check_url(url: &str, schemes: &[&str]) -> Result<String, PragmaError> {
+    let parsed = reqwest::Url::parse(url.trim())
+        .map_err(|e| PragmaError::InvalidConfig(format!("invalid URL {url:?}: {e}")))?;
+    if !schemes.contains(&parsed.scheme()) {
+        return Err(PragmaError::InvalidConfig(format!(
+            "unsupported scheme for {url:?}, expected one of {schemes:?}"
+        )));
+    }
+    Ok(parsed.as_str().trim_end_matches('/').to_string())
+}
+
+/// Returns the WebSocket URL served by the same host as the HTTP `url`, as returned by
+/// `check_url`.
+fn ws_url_of(http_url: &str) -> String {
+    match http_url.split_once("://") {
+        Some(("https", rest)) => format!("wss://{rest}"),
+        Some((_, rest)) => format!("ws://{rest}"),
+        None => http_url.to_string(),
+    }
+}
+
 /// Environment options for the Pragma API.
+///
+/// # Examples
+///
+/// ```
+/// use pragma_rs::Environment;
+///
+/// assert!(matches!("dev".parse(), Ok(Environment::Development)));
+/// assert!(matches!("Production".parse(), Ok(Environment::Production)));
+///
+/// // A custom deployment, whose WebSocket URL is derived from its HTTP URL.
+/// let Ok(Environment::Local { http_base_url, ws_base_url }) = "https://pragma.internal/".parse()
+/// else {
+///     panic!("not a local environment");
+/// };
+/// assert_eq!(http_base_url, "https://pragma.internal");
+/// assert_eq!(ws_base_url, "wss://pragma.internal");
+///
+/// assert!("ftp://pragma.internal".parse::<Environment>().is_err());
+/// ```
 #[derive(Debug, Clone)]
 pub enum Environment {
     Local {
@@ -7,11 +67,109 @@ pub enum Environment {
     },
     Development,
     Production,
+    /// Serves the traffic stored in a cassette recorded with [`Config::record_to`], without any network access.
+    Replay {
+        cassette: PathBuf,
+        timing: ReplayTiming,
+    },
+}
+
+impl Environment {
+    /// Returns the URLs of the environment, and its cassette for `Replay`.
+    fn into_endpoint(self) -> (Endpoint, Option<(PathBuf, ReplayTiming)>) {
+        let mut replay = None;
+        let (http_url, ws_url) = match self {
+            Environment::Local {
+                http_base_url,
+                ws_base_url,
+            } => (http_base_url, ws_base_url),
+            Environment::Development => (
+                "https://api.devnet.pragma.build".to_string(),
+                "wss://api.devnet.pragma.build".to_string(),
+            ),
+            Environment::Production => (
+                "https://api.production.pragma.build".to_string(),
+                "wss://api.production.pragma.build".to_string(),
+            ),
+            Environment::Replay { cassette, timing } => {
+                replay = Some((cassette, timing));
+                ("http://replay".to_string(), "ws://replay".to_string())
+            }
+        };
+        (Endpoint { http_url, ws_url }, replay)
+    }
+}
+
+impl FromStr for Environment {
+    type Err = PragmaError;
+
+    /// Parses "dev" (or "development", "devnet"), "prod" (or "production"), case-insensitive, or
+    /// the HTTP base URL of a custom deployment.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "dev" | "development" | "devnet" => Ok(Self::Development),
+            "prod" | "production" => Ok(Self::Production),
+            _ if s.contains("://") => {
+                let http_base_url = check_url(s, HTTP_SCHEMES)?;
+                Ok(Self::Local {
+                    ws_base_url: ws_url_of(&http_base_url),
+                    http_base_url,
+                })
+            }
+            _ => Err(PragmaError::InvalidConfig(format!(
+                "unknown environment {s:?}, expected \"dev\", \"prod\" or a URL"
+            ))),
+        }
+    }
+}
+
+/// The settings of a `Config` read from the environment or a file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Settings {
+    api_key: Option<String>,
+    environment: Option<String>,
+    http_url: Option<String>,
+    ws_url: Option<String>,
+}
+
+impl Settings {
+    fn from_env() -> Self {
+        let var = |name| std::env::var(name).ok().filter(|value| !value.is_empty());
+        Self {
+            api_key: var(ENV_API_KEY),
+            environment: var(ENV_ENVIRONMENT),
+            http_url: var(ENV_HTTP_URL),
+            ws_url: var(ENV_WS_URL),
+        }
+    }
+
+    fn into_config(self) -> Result<Config, PragmaError> {
+        let api_key = self
+            .api_key
+            .ok_or_else(|| PragmaError::InvalidConfig(format!("{ENV_API_KEY} is not set")))?;
+        let environment = match self.environment {
+            Some(environment) => environment.parse()?,
+            None => Environment::Development,
+        };
+
+        let mut config = Config::new(api_key, environment);
+        if let Some(http_url) = self.http_url {
+            config.base_url = check_url(&http_url, HTTP_SCHEMES)?;
+            config.ws_url = ws_url_of(&config.base_url);
+        }
+        if let Some(ws_url) = self.ws_url {
+            config.ws_url = check_url(&ws_url, WS_SCHEMES)?;
+        }
+        config.validate()?;
+        Ok(config)
+    }
 }
 
 /// Configuration for the Pragma SDK.
 ///
-/// Holds the API key and environment-specific URLs for HTTP and WebSocket connections.
+/// Holds the API key and environment-specific URLs for HTTP and WebSocket connections. The `Debug`
+/// output of the config does not include the API key.
 ///
 /// # Examples
 ///
@@ -20,35 +178,234 @@ pub enum Environment {
 ///
 /// let config = Config::new("your_api_key".to_string(), Environment::Development);
 /// ```
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Config {
-    pub(crate) api_key: String,
+    pub(crate) api_key: SharedApiKey,
     pub(crate) base_url: String,
-    #[allow(unused)]
     pub(crate) ws_url: String,
+    pub(crate) fallbacks: Vec<Endpoint>,
+    pub(crate) failover: FailoverConfig,
+    pub(crate) ws_reconnect: Option<ReconnectConfig>,
+    pub(crate) health_timeout: Duration,
+    pub(crate) record_to: Option<PathBuf>,
+    pub(crate) replay: Option<(PathBuf, ReplayTiming)>,
+    pub(crate) entry_cache: Option<EntryCacheConfig>,
+    pub(crate) rate_limit: Option<RateLimitConfig>,
+    pub(crate) clock: SharedClock,
 }
 
 impl Config {
     /// Creates a new `Config` instance with the given API key and environment.
-    pub fn new(api_key: String, environment: Environment) -> Self {
-        let (base_url, ws_url) = match environment {
-            Environment::Local {
-                http_base_url,
-                ws_base_url,
-            } => (http_base_url, ws_base_url),
-            Environment::Development => (
-                "https://api.devnet.pragma.build".to_string(),
-                "wss://api.devnet.pragma.build".to_string(),
-            ),
-            Environment::Production => (
-                "https://api.production.pragma.build".to_string(),
-                "wss://api.production.pragma.build".to_string(),
-            ),
-        };
+    pub fn new(api_key: impl Into<ApiKey>, environment: Environment) -> Self {
+        let (Endpoint { http_url, ws_url }, replay) = environment.into_endpoint();
         Self {
-            api_key,
-            base_url,
+            api_key: SharedApiKey::new(api_key.into()),
+            base_url: http_url,
             ws_url,
+            fallbacks: Vec::new(),
+            failover: FailoverConfig::default(),
+            ws_reconnect: None,
+            health_timeout: DEFAULT_HEALTH_TIMEOUT,
+            record_to: None,
+            replay,
+            entry_cache: None,
+            rate_limit: None,
+            clock: SharedClock::default(),
+        }
+    }
+
+    /// Creates a `Config` from the environment variables:
+    ///
+    /// * `PRAGMA_API_KEY`: the API key, required.
+    /// * `PRAGMA_ENVIRONMENT`: "dev", "prod" or the HTTP base URL of a custom deployment, see the
+    ///   `FromStr` implementation of [`Environment`]. Defaults to "dev".
+    /// * `PRAGMA_HTTP_URL`: overrides the HTTP base URL of the environment, and its WebSocket URL
+    ///   with the same host.
+    /// * `PRAGMA_WS_URL`: overrides the WebSocket base URL.
+    ///
+    /// Empty variables are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pragma_rs::{Config, PragmaClient};
+    ///
+    /// // With PRAGMA_API_KEY=your_api_key and PRAGMA_ENVIRONMENT=prod.
+    /// let config = Config::from_env()?;
+    /// let client = PragmaClient::new(config)?;
+    /// # Ok::<(), pragma_rs::PragmaError>(())
+    /// ```
+    pub fn from_env() -> Result<Self, PragmaError> {
+        Settings::from_env().into_config()
+    }
+
+    /// Creates a `Config` from a TOML or JSON file, according to its extension, with the optional
+    /// fields `api_key`, `environment`, `http_url` and `ws_url` of [`Config::from_env`]. Without
+    /// `api_key`, the `PRAGMA_API_KEY` environment variable is used, so the file can be free of
+    /// secrets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pragma_rs::Config;
+    ///
+    /// let path = std::env::temp_dir().join("pragma.toml");
+    /// std::fs::write(&path, "api_key = \"your_api_key\"\nenvironment = \"dev\"\n").unwrap();
+    /// let config = Config::from_file(&path).unwrap();
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    #[cfg(feature = "config-file")]
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self, PragmaError> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)?;
+        let mut settings: Settings = match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => toml::from_str(&content)
+                .map_err(|e| PragmaError::InvalidConfig(format!("{}: {e}", path.display())))?,
+            Some("json") => serde_json::from_str(&content)
+                .map_err(|e| PragmaError::InvalidConfig(format!("{}: {e}", path.display())))?,
+            _ => {
+                return Err(PragmaError::InvalidConfig(format!(
+                    "{}: expected a .toml or .json file",
+                    path.display()
+                )))
+            }
+        };
+        if settings.api_key.is_none() {
+            settings.api_key = Settings::from_env().api_key;
+        }
+        settings.into_config()
+    }
+
+    /// Checks that the HTTP URL uses the `http` or `https` scheme, the WebSocket URL `ws` or
+    /// `wss`, and that the rate limits are positive. Called by `PragmaClient::new`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pragma_rs::{Config, Environment, PragmaClient};
+    ///
+    /// let environment = Environment::Local {
+    ///     http_base_url: "localhost:3000".to_string(),
+    ///     ws_base_url: "ws://localhost:3000".to_string(),
+    /// };
+    /// let config = Config::new("your_api_key".to_string(), environment);
+    /// assert!(config.validate().is_err());
+    /// assert!(PragmaClient::new(config).is_err());
+    /// ```
+    pub fn validate(&self) -> Result<(), PragmaError> {
+        check_url(&self.base_url, HTTP_SCHEMES)?;
+        check_url(&self.ws_url, WS_SCHEMES)?;
+        for fallback in &self.fallbacks {
+            check_url(&fallback.http_url, HTTP_SCHEMES)?;
+            check_url(&fallback.ws_url, WS_SCHEMES)?;
+        }
+        if let Some(rate_limit) = &self.rate_limit {
+            rate_limit.validate()?;
         }
+        Ok(())
+    }
+
+    /// Returns the HTTP base URL of the primary endpoint.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Returns the WebSocket base URL of the primary endpoint.
+    pub fn ws_url(&self) -> &str {
+        &self.ws_url
+    }
+
+    /// Adds a fallback endpoint, used when the previous ones are failing. Fallbacks are tried in
+    /// the order they are added, after the environment of [`Config::new`]. `Environment::Replay`
+    /// is not a valid fallback and is ignored.
+    ///
+    /// HTTP requests and WebSocket connections are sent to the active endpoint, see
+    /// [`FailoverConfig`] for when it changes. With fallbacks, the WebSocket clients reconnect to
+    /// the active endpoint when their connection is lost or when it fails back, see
+    /// [`Config::with_ws_reconnect`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pragma_rs::{Config, Environment};
+    ///
+    /// let mirror = Environment::Local {
+    ///     http_base_url: "https://pragma-mirror.internal".to_string(),
+    ///     ws_base_url: "wss://pragma-mirror.internal".to_string(),
+    /// };
+    /// let config = Config::new("your_api_key".to_string(), Environment::Production)
+    ///     .with_fallback(mirror);
+    /// ```
+    pub fn with_fallback(mut self, environment: Environment) -> Self {
+        if !matches!(environment, Environment::Replay { .. }) {
+            self.fallbacks.push(environment.into_endpoint().0);
+        }
+        self
+    }
+
+    /// Sets when the clients switch between the endpoints, see [`Config::with_fallback`].
+    pub fn with_failover(mut self, failover: FailoverConfig) -> Self {
+        self.failover = failover;
+        self
+    }
+
+    /// Sets how the WebSocket clients reconnect after losing their connection. They only reconnect
+    /// by default when the config has fallbacks, with `ReconnectConfig::default()`.
+    pub fn with_ws_reconnect(mut self, reconnect: ReconnectConfig) -> Self {
+        self.ws_reconnect = Some(reconnect);
+        self
+    }
+
+    /// Returns how the WebSocket clients reconnect, if they do.
+    pub(crate) fn ws_reconnect(&self) -> Option<ReconnectConfig> {
+        self.ws_reconnect
+            .or_else(|| (!self.fallbacks.is_empty()).then(ReconnectConfig::default))
+    }
+
+    /// Sets the timeout of the health checks, `DEFAULT_HEALTH_TIMEOUT` by default. See
+    /// [`crate::PragmaClient::health`].
+    pub fn with_health_timeout(mut self, timeout: Duration) -> Self {
+        self.health_timeout = timeout;
+        self
+    }
+
+    /// Records every HTTP exchange and WebSocket frame of the clients built from this config to a
+    /// JSONL cassette at `path`, which can be replayed with `Environment::Replay`.
+    ///
+    /// The cassette is created, or truncated, when the `PragmaClient` is built.
+    pub fn record_to(mut self, path: impl Into<PathBuf>) -> Self {
+        self.record_to = Some(path.into());
+        self
+    }
+
+    /// Caches the `get_entry` responses of the clients built from this config, per pair and parameters.
+    ///
+    /// The cache is shared by the clones of a `PragmaClient`.
+    pub fn with_entry_cache(mut self, cache: EntryCacheConfig) -> Self {
+        self.entry_cache = Some(cache);
+        self
+    }
+
+    /// Rate limits the HTTP requests of the clients built from this config.
+    ///
+    /// The limiter is shared by the clones of a `PragmaClient`.
+    pub fn with_rate_limit(mut self, rate_limit: RateLimitConfig) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// Reads the API key from `provider` before each request, instead of the key given to
+    /// [`Config::new`], e.g. to rotate it without rebuilding the `PragmaClient`.
+    pub fn with_api_key_provider(mut self, provider: impl ApiKeyProvider + 'static) -> Self {
+        self.api_key = SharedApiKey::new(provider);
+        self
+    }
+
+    /// Sets the clock used to compute the age of prices checked against a `max_age`.
+    ///
+    /// Defaults to the system time, a [`crate::ManualClock`] makes staleness checks testable.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = SharedClock(Arc::new(clock));
+        self
     }
 }
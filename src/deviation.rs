@@ -0,0 +1,250 @@
+use std::{sync::Arc, time::Duration};
+
+use futures_util::future::join_all;
+use pragma_common::starknet::StarknetNetwork;
+use tokio::sync::mpsc;
+
+use crate::{GetOnchainEntryParams, PragmaApi, PragmaError, Timestamp};
+
+/// Default period between two checks of a `DeviationMonitor`.
+pub const DEFAULT_DEVIATION_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A comparison of the offchain and onchain prices of a pair.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deviation {
+    /// The trading pair (e.g., "BTC/USD").
+    pub pair: String,
+
+    /// The offchain price, scaled by its decimals.
+    pub offchain_price: f64,
+
+    /// The onchain price, scaled by its decimals.
+    pub onchain_price: f64,
+
+    /// The timestamp of the offchain price.
+    pub offchain_timestamp: Timestamp,
+
+    /// The timestamp of the last onchain update.
+    pub onchain_timestamp: Timestamp,
+
+    /// The difference between both prices, in basis points of the offchain price.
+    pub deviation_bps: f64,
+
+    /// The time between both timestamps.
+    pub lag: Duration,
+}
+
+impl Deviation {
+    fn new(
+        pair: String,
+        (offchain_price, offchain_timestamp): (f64, Timestamp),
+        (onchain_price, onchain_timestamp): (f64, Timestamp),
+    ) -> Self {
+        let deviation_bps = if offchain_price == 0.0 {
+            if onchain_price == 0.0 {
+                0.0
+            } else {
+                f64::INFINITY
+            }
+        } else {
+            (onchain_price - offchain_price).abs() / offchain_price.abs() * 10_000.0
+        };
+        let lag = offchain_timestamp
+            .saturating_duration_since(onchain_timestamp)
+            .max(onchain_timestamp.saturating_duration_since(offchain_timestamp));
+
+        Self {
+            pair,
+            offchain_price,
+            onchain_price,
+            offchain_timestamp,
+            onchain_timestamp,
+            deviation_bps,
+            lag,
+        }
+    }
+}
+
+/// An event emitted by a running `DeviationMonitor`.
+#[derive(Debug)]
+pub enum DeviationEvent {
+    /// The prices of the pair differ by more than the `max_deviation_bps` of the monitor.
+    PriceDeviation(Deviation),
+
+    /// The timestamps of the pair differ by more than the `max_lag` of the monitor.
+    TimestampLag(Deviation),
+
+    /// One of the prices of the pair could not be fetched.
+    Error { pair: String, error: PragmaError },
+}
+
+/// Periodically compares the offchain price of pairs (`get_entry`) to their onchain price on a
+/// Starknet network (`get_onchain_entry`).
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use pragma_rs::{Config, DeviationEvent, DeviationMonitor, Environment, PragmaClient, StarknetNetwork};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let config = Config::new("your_api_key".to_string(), Environment::Development);
+///     let client = PragmaClient::new(config)?;
+///     let mut events = DeviationMonitor::new(client, StarknetNetwork::Mainnet)
+///         .pair("BTC", "USD")
+///         .pair("ETH", "USD")
+///         .max_deviation_bps(25.0)
+///         .max_lag(Duration::from_secs(120))
+///         .interval(Duration::from_secs(30))?
+///         .start();
+///
+///     while let Some(event) = events.recv().await {
+///         if let DeviationEvent::PriceDeviation(deviation) = event {
+///             println!("{} deviates by {:.1} bps", deviation.pair, deviation.deviation_bps);
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct DeviationMonitor {
+    api: Arc<dyn PragmaApi>,
+    network: StarknetNetwork,
+    pairs: Vec<(String, String)>,
+    interval: Duration,
+    max_deviation_bps: f64,
+    max_lag: Option<Duration>,
+}
+
+impl std::fmt::Debug for DeviationMonitor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeviationMonitor")
+            .field("network", &self.network)
+            .field("pairs", &self.pairs)
+            .field("interval", &self.interval)
+            .field("max_deviation_bps", &self.max_deviation_bps)
+            .field("max_lag", &self.max_lag)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DeviationMonitor {
+    /// Creates a monitor without pairs, checking every `DEFAULT_DEVIATION_INTERVAL` for
+    /// deviations above 50 bps and ignoring the timestamp lag.
+    pub fn new(api: impl PragmaApi + 'static, network: StarknetNetwork) -> Self {
+        Self {
+            api: Arc::new(api),
+            network,
+            pairs: Vec::new(),
+            interval: DEFAULT_DEVIATION_INTERVAL,
+            max_deviation_bps: 50.0,
+            max_lag: None,
+        }
+    }
+
+    /// Adds a pair to monitor.
+    pub fn pair(mut self, base: impl Into<String>, quote: impl Into<String>) -> Self {
+        self.pairs.push((base.into(), quote.into()));
+        self
+    }
+
+    /// Sets the period between two checks, or fails with `PragmaError::InvalidConfig` if
+    /// `interval` is zero.
+    pub fn interval(mut self, interval: Duration) -> Result<Self, PragmaError> {
+        if interval.is_zero() {
+            return Err(PragmaError::InvalidConfig(
+                "the deviation monitor interval must be positive".to_string(),
+            ));
+        }
+        self.interval = interval;
+        Ok(self)
+    }
+
+    /// Emits a `PriceDeviation` when the prices differ by more than `bps` basis points.
+    pub fn max_deviation_bps(mut self, bps: f64) -> Self {
+        self.max_deviation_bps = bps;
+        self
+    }
+
+    /// Emits a `TimestampLag` when the timestamps differ by more than `lag`.
+    pub fn max_lag(mut self, lag: Duration) -> Self {
+        self.max_lag = Some(lag);
+        self
+    }
+
+    /// Fetches both prices of a pair and compares them.
+    pub async fn check(&self, base: &str, quote: &str) -> Result<Deviation, PragmaError> {
+        let params = GetOnchainEntryParams {
+            network: self.network,
+            ..Default::default()
+        };
+        let (offchain, onchain) = futures_util::try_join!(
+            self.api.get_entry(base, quote, None),
+            self.api.get_onchain_entry(base, quote, params),
+        )?;
+
+        let offchain_price = offchain
+            .price_f64()
+            .map_err(|e| PragmaError::InvalidPrice(format!("{}: {e}", offchain.price)))?;
+        let onchain_price = onchain
+            .price_f64()
+            .map_err(|e| PragmaError::InvalidPrice(format!("{}: {e}", onchain.price)))?;
+
+        Ok(Deviation::new(
+            format!("{base}/{quote}"),
+            (offchain_price, offchain.timestamp),
+            (onchain_price, onchain.last_updated_timestamp),
+        ))
+    }
+
+    /// Checks every pair once, and returns the events for the thresholds exceeded.
+    pub async fn check_all(&self) -> Vec<DeviationEvent> {
+        let checks = self
+            .pairs
+            .iter()
+            .map(|(base, quote)| async move { (base, quote, self.check(base, quote).await) });
+
+        let mut events = Vec::new();
+        for (base, quote, result) in join_all(checks).await {
+            match result {
+                Ok(deviation) => {
+                    let lagging = self.max_lag.is_some_and(|max_lag| deviation.lag > max_lag);
+                    if deviation.deviation_bps > self.max_deviation_bps {
+                        events.push(DeviationEvent::PriceDeviation(deviation.clone()));
+                    }
+                    if lagging {
+                        events.push(DeviationEvent::TimestampLag(deviation));
+                    }
+                }
+                Err(error) => events.push(DeviationEvent::Error {
+                    pair: format!("{base}/{quote}"),
+                    error,
+                }),
+            }
+        }
+        events
+    }
+
+    /// Checks every pair at each interval in a background task, and returns the receiver of the
+    /// events. The task stops when the receiver is dropped.
+    pub fn start(self) -> mpsc::UnboundedReceiver<DeviationEvent> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = sender.closed() => break,
+                }
+                for event in self.check_all().await {
+                    if sender.send(event).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        receiver
+    }
+}
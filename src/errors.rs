@@ -15,7 +15,11 @@ pub enum PragmaError {
     #[error("Could not build Pragma client")]
     BuildingClient,
 
-    /// `PragmaError` parsing JSON data.
+    /// The configuration is incomplete or invalid, e.g. a URL with an unsupported scheme.
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+
+    /// `PragmaError` parsing JSON data, including the body of a successful HTTP response.
     #[error("JSON parsing failed: {0}")]
     JsonError(#[from] serde_json::Error),
 
@@ -31,4 +35,40 @@ pub enum PragmaError {
 
     #[error("Channel error: {0}")]
     ChannelError(String),
+
+    /// The funding rate source is not known by the SDK.
+    #[error("Unknown funding rate source: {0}")]
+    UnknownFundingSource(String),
+
+    /// Too many requests (HTTP 429).
+    #[error("Rate limited, retry after {retry_after:?}")]
+    RateLimited {
+        retry_after: Option<std::time::Duration>,
+    },
+
+    /// No response was recorded in the replayed cassette for this request.
+    #[error("No recorded response for {0}")]
+    CassetteMiss(String),
+
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    /// A price returned by the API is not a valid hexadecimal number.
+    #[error("Invalid price: {0}")]
+    InvalidPrice(String),
+
+    /// The price is older than the `max_age` requested.
+    #[error("Stale price: published {age:?} ago, max age is {max_age:?}")]
+    StalePrice {
+        age: std::time::Duration,
+        max_age: std::time::Duration,
+    },
+
+    /// No price is available for the pair.
+    #[error("No price available for {0}")]
+    PriceUnavailable(String),
+
+    /// The timestamp cannot be represented by the target type.
+    #[error("Timestamp out of range: {0}")]
+    TimestampOutOfRange(String),
 }
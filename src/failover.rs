@@ -0,0 +1,207 @@
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use crate::{telemetry::trace_event, Config};
+
+/// When a client switches between the endpoints of its `Config`, see [`Config::with_fallback`].
+///
+/// The client sends its requests to the active endpoint, the primary one at first. Once
+/// `max_error_rate` of the last `window` HTTP requests failed (with at least `min_requests`
+/// requests), or a WebSocket connection failed, it fails over to the next endpoint.
+///
+/// While on a fallback, the endpoints preferred to the active one are probed every
+/// `probe_interval`. The client fails back to the first of them which passed every probe for
+/// `failback_after`, so a flapping endpoint does not get the traffic back.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FailoverConfig {
+    /// The share of failed requests, between 0 and 1, after which the active endpoint is left.
+    /// Transport errors and `5xx` responses are failures. With 0, any failure is enough.
+    pub max_error_rate: f64,
+
+    /// The minimum number of requests to the active endpoint before its error rate is checked.
+    pub min_requests: usize,
+
+    /// The number of latest requests the error rate is computed over.
+    pub window: usize,
+
+    /// The time between two probes of the preferred endpoints, while on a fallback.
+    pub probe_interval: Duration,
+
+    /// The timeout of a probe.
+    pub probe_timeout: Duration,
+
+    /// How long a preferred endpoint must stay healthy before the client fails back to it.
+    pub failback_after: Duration,
+}
+
+impl Default for FailoverConfig {
+    fn default() -> Self {
+        Self {
+            max_error_rate: 0.5,
+            min_requests: 5,
+            window: 20,
+            probe_interval: Duration::from_secs(10),
+            probe_timeout: Duration::from_secs(2),
+            failback_after: Duration::from_secs(30),
+        }
+    }
+}
+
+/// The HTTP and WebSocket base URLs of a deployment of the Pragma API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Endpoint {
+    pub(crate) http_url: String,
+    pub(crate) ws_url: String,
+}
+
+#[derive(Debug)]
+struct State {
+    active: usize,
+    /// The outcomes of the latest requests to the active endpoint.
+    outcomes: VecDeque<bool>,
+    probing: bool,
+    probed_at: Option<Instant>,
+    /// Since when each endpoint passed every probe.
+    healthy_since: Vec<Option<Instant>>,
+}
+
+/// The endpoints of a client, ordered by preference, and the active one.
+#[derive(Debug)]
+pub(crate) struct Endpoints {
+    endpoints: Vec<Endpoint>,
+    pub(crate) config: FailoverConfig,
+    state: Mutex<State>,
+}
+
+impl Endpoints {
+    pub(crate) fn new(config: &Config) -> Self {
+        let mut endpoints = vec![Endpoint {
+            http_url: config.base_url.clone(),
+            ws_url: config.ws_url.clone(),
+        }];
+        endpoints.extend(config.fallbacks.iter().cloned());
+        Self {
+            state: Mutex::new(State {
+                active: 0,
+                outcomes: VecDeque::new(),
+                probing: false,
+                probed_at: None,
+                healthy_since: vec![None; endpoints.len()],
+            }),
+            endpoints,
+            config: config.failover,
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.endpoints.len()
+    }
+
+    /// Returns the active endpoint and its index.
+    pub(crate) fn active(&self) -> (usize, &Endpoint) {
+        let active = self.state().active;
+        (active, &self.endpoints[active])
+    }
+
+    /// Records the outcome of a request sent to the endpoint `index`, and fails over if the
+    /// error rate of the active endpoint is too high.
+    pub(crate) fn observe(&self, index: usize, success: bool) {
+        let mut state = self.state();
+        if index != state.active || self.endpoints.len() < 2 {
+            return;
+        }
+        state.outcomes.push_back(success);
+        while state.outcomes.len() > self.config.window.max(1) {
+            state.outcomes.pop_front();
+        }
+
+        let requests = state.outcomes.len();
+        let errors = state.outcomes.iter().filter(|success| !**success).count();
+        if requests >= self.config.min_requests.max(1)
+            && errors > 0
+            && errors as f64 >= self.config.max_error_rate * requests as f64
+        {
+            self.fail_over(&mut state);
+        }
+    }
+
+    /// Fails over from the endpoint `index`, e.g. after a connection failure, if it is active.
+    pub(crate) fn fail(&self, index: usize) {
+        let mut state = self.state();
+        if index == state.active && self.endpoints.len() > 1 {
+            self.fail_over(&mut state);
+        }
+    }
+
+    fn fail_over(&self, state: &mut State) {
+        let from = state.active;
+        state.active = (from + 1) % self.endpoints.len();
+        state.outcomes.clear();
+        state.healthy_since[from] = None;
+        trace_event!(
+            warn,
+            from = %self.endpoints[from].http_url,
+            to = %self.endpoints[state.active].http_url,
+            "failing over to the next endpoint"
+        );
+    }
+
+    /// Returns the endpoints preferred to the active one if they are due for a probe, and marks
+    /// the probe as started.
+    pub(crate) fn start_probe(&self) -> Option<Vec<(usize, Endpoint)>> {
+        let mut state = self.state();
+        let due = state
+            .probed_at
+            .map_or(true, |at| at.elapsed() >= self.config.probe_interval);
+        if state.active == 0 || state.probing || !due {
+            return None;
+        }
+        state.probing = true;
+        Some(
+            self.endpoints[..state.active]
+                .iter()
+                .cloned()
+                .enumerate()
+                .collect(),
+        )
+    }
+
+    /// Records the results of a probe started with [`Self::start_probe`], and fails back to the
+    /// first endpoint which stayed healthy for `failback_after`.
+    pub(crate) fn end_probe(&self, results: Vec<(usize, bool)>) {
+        let mut state = self.state();
+        let now = Instant::now();
+        state.probing = false;
+        state.probed_at = Some(now);
+        for (index, healthy) in results {
+            let since = &mut state.healthy_since[index];
+            *since = match (healthy, *since) {
+                (true, Some(since)) => Some(since),
+                (true, None) => Some(now),
+                (false, _) => None,
+            };
+        }
+
+        let failback = (0..state.active).find(|index| {
+            state.healthy_since[*index]
+                .is_some_and(|since| now.duration_since(since) >= self.config.failback_after)
+        });
+        if let Some(to) = failback {
+            trace_event!(
+                info,
+                from = %self.endpoints[state.active].http_url,
+                to = %self.endpoints[to].http_url,
+                "failing back to a preferred endpoint"
+            );
+            state.active = to;
+            state.outcomes.clear();
+        }
+    }
+}
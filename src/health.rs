@@ -0,0 +1,166 @@
+use std::time::Duration;
+
+use tokio::{sync::watch, task::JoinHandle};
+
+use crate::{telemetry::trace_event, PragmaClient, PragmaError};
+
+/// Default timeout of a health check, see [`crate::Config::with_health_timeout`].
+pub const DEFAULT_HEALTH_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The response header read as the version of the server, when present.
+pub(crate) const VERSION_HEADER: &str = "x-pragma-version";
+
+/// The result of a health check of the Pragma API, see [`PragmaClient::health`].
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use pragma_rs::Health;
+///
+/// let health = Health {
+///     url: "https://api.devnet.pragma.build/node".to_string(),
+///     reachable: true,
+///     status: Some(503),
+///     latency: Duration::from_millis(42),
+///     version: None,
+///     error: Some("API returned status 503 Service Unavailable".to_string()),
+/// };
+/// assert!(!health.is_healthy());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Health {
+    /// The URL checked, on the active endpoint of the client.
+    pub url: String,
+
+    /// Whether the API answered, whatever the status of its response.
+    pub reachable: bool,
+
+    /// The status of the response, if any.
+    pub status: Option<u16>,
+
+    /// The round-trip time of the check, until the response headers or the error.
+    pub latency: Duration,
+
+    /// The version of the server, if it sends the `x-pragma-version` header.
+    pub version: Option<String>,
+
+    /// Why the check failed: the transport error or the unsuccessful status and its body.
+    pub error: Option<String>,
+}
+
+impl Health {
+    /// Returns true if the API answered with a success status.
+    pub fn is_healthy(&self) -> bool {
+        self.status
+            .is_some_and(|status| (200..300).contains(&status))
+    }
+
+    /// Returns true if both checks report the same state, ignoring their latency.
+    fn same_state(&self, other: &Self) -> bool {
+        self.url == other.url
+            && self.reachable == other.reachable
+            && self.status == other.status
+            && self.version == other.version
+            && self.error == other.error
+    }
+}
+
+/// Checks the health of the API periodically in a background task, created by
+/// [`PragmaClient::watch_health`].
+///
+/// The latest check is always available from [`HealthWatcher::current`], while
+/// [`HealthWatcher::changed`] only wakes up when the state changes: reachability, status, version,
+/// error or endpoint, but not latency alone. Dropping the watcher stops the task.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use pragma_rs::{Config, Environment, PragmaClient};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), pragma_rs::PragmaError> {
+///     let config = Config::new("your_api_key".to_string(), Environment::Development);
+///     let client = PragmaClient::new(config)?;
+///     let mut watcher = client.watch_health(Duration::from_secs(10)).await?;
+///
+///     while let Some(health) = watcher.changed().await {
+///         match health.error {
+///             None => println!("API is up ({:?})", health.latency),
+///             Some(error) => println!("API is down: {error}"),
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct HealthWatcher {
+    receiver: watch::Receiver<Health>,
+    task: JoinHandle<()>,
+}
+
+impl Drop for HealthWatcher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl HealthWatcher {
+    /// Checks the health of `client` once, then every `interval` in a background task.
+    pub(crate) async fn start(
+        client: PragmaClient,
+        interval: Duration,
+    ) -> Result<Self, PragmaError> {
+        if interval.is_zero() {
+            return Err(PragmaError::InvalidConfig(
+                "the health check interval must be positive".to_string(),
+            ));
+        }
+        let (sender, receiver) = watch::channel(client.health().await);
+        let task = tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            ticks.tick().await;
+            loop {
+                ticks.tick().await;
+                let health = client.health().await;
+                sender.send_if_modified(|current| {
+                    let changed = !current.same_state(&health);
+                    if changed {
+                        trace_event!(
+                            info,
+                            url = %health.url,
+                            healthy = health.is_healthy(),
+                            status = health.status,
+                            error = health.error.as_deref(),
+                            "health status changed"
+                        );
+                    }
+                    *current = health;
+                    changed
+                });
+            }
+        });
+        Ok(Self { receiver, task })
+    }
+
+    /// Returns the latest check.
+    pub fn current(&self) -> Health {
+        self.receiver.borrow().clone()
+    }
+
+    /// Waits for the state to change, and returns the check reporting it.
+    ///
+    /// Returns `None` if the task stopped, which only happens if it panicked.
+    pub async fn changed(&mut self) -> Option<Health> {
+        self.receiver.changed().await.ok()?;
+        Some(self.receiver.borrow_and_update().clone())
+    }
+
+    /// Returns a receiver of the checks, notified when the state changes. The receiver is
+    /// closed once the watcher is dropped.
+    pub fn subscribe(&self) -> watch::Receiver<Health> {
+        self.receiver.clone()
+    }
+}
@@ -1,26 +1,322 @@
 pub(crate) mod offchain;
 pub(crate) mod onchain;
 
-use crate::PragmaClient;
+use std::time::Duration;
+
+use reqwest::{header::HeaderMap, StatusCode};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    cassette::{cassette_url, CassetteEntry},
+    failover::Endpoint,
+    health::{Health, HealthWatcher, VERSION_HEADER},
+    rate_limit::retry_after,
+    telemetry::{self, trace_event, trace_record},
+    PragmaClient, PragmaError, Timestamp,
+};
+
+/// Maps non-success statuses to a `PragmaError` and decodes the body of successful responses.
+pub(crate) fn decode_response<T: DeserializeOwned>(
+    status: StatusCode,
+    body: String,
+    retry_after: Option<Duration>,
+) -> Result<T, PragmaError> {
+    match status {
+        StatusCode::UNAUTHORIZED => Err(PragmaError::Unauthorized(body)),
+        StatusCode::TOO_MANY_REQUESTS => Err(PragmaError::RateLimited { retry_after }),
+        status if !status.is_success() => Err(PragmaError::ApiError(format!(
+            "API returned status {status}: {body}",
+        ))),
+        _ => serde_json::from_str(&body).map_err(PragmaError::JsonError),
+    }
+}
 
 impl PragmaClient {
-    /// Checks if the Pragma API is available by making a ping request.
-    /// Returns true if the API responds successfully, false otherwise.
-    pub async fn is_healthy(&self) -> bool {
-        let url = format!("{}/node", self.config.base_url);
+    /// Sends a GET request to `path` and decodes the JSON response.
+    ///
+    /// Every HTTP endpoint goes through this method, which handles rate limiting, tracing, metrics,
+    /// and recording and replaying cassettes.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "pragma_http_request",
+            level = "debug",
+            skip_all,
+            fields(
+                endpoint = crate::telemetry::endpoint(path).0,
+                pair = crate::telemetry::endpoint(path).1,
+                url = tracing::field::Empty,
+                status = tracing::field::Empty,
+                latency_ms = tracing::field::Empty,
+                throttled_ms = tracing::field::Empty,
+                retries = tracing::field::Empty,
+                replayed = self.replayer.is_some(),
+            )
+        )
+    )]
+    pub(crate) async fn get_json<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<T, PragmaError> {
+        let mut retries = 0u32;
+        let response = loop {
+            let started = std::time::Instant::now();
+            let response = self.send_get(path, query).await;
+            let latency = started.elapsed();
+
+            let status = response.as_ref().ok().map(|(status, _, _)| *status);
+            telemetry::http_request(path, status, latency);
+            trace_record!(
+                "latency_ms" = latency.as_millis() as u64,
+                "status" = status.map(|s| s.as_u16()),
+            );
+
+            // The rate limiter holds the retry until `Retry-After` has elapsed.
+            let throttled = matches!(response, Ok((StatusCode::TOO_MANY_REQUESTS, _, Some(_))));
+            if throttled && retries == 0 && self.rate_limiter.is_some() {
+                trace_event!(debug, "rate limited, retrying after the Retry-After delay");
+                retries += 1;
+                continue;
+            }
+            break response;
+        };
+        trace_record!("retries" = retries);
+
+        let result = response
+            .and_then(|(status, body, retry_after)| decode_response(status, body, retry_after));
+        #[cfg(feature = "tracing")]
+        if let Err(e) = &result {
+            tracing::warn!(error = %e, "request failed");
+        } else {
+            tracing::debug!("request completed");
+        }
+        result
+    }
+
+    /// Sends a GET request, or reads its response from the cassette, and returns the status, body
+    /// and `Retry-After` delay of the response.
+    async fn send_get(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<(StatusCode, String, Option<Duration>), PragmaError> {
+        let (endpoint, Endpoint { http_url, .. }) = self.endpoints.active();
+        let request = self
+            .http_client
+            .get(format!("{http_url}{path}"))
+            .header("x-api-key", self.config.api_key.get().header_value()?)
+            .query(query)
+            .build()?;
+        let url = cassette_url(request.url());
+        trace_record!("url" = url.as_str());
+
+        match &self.replayer {
+            Some(replayer) => {
+                let (status, body) = replayer.http("GET", &url)?;
+                let status =
+                    StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+                Ok((status, body, None))
+            }
+            None => {
+                self.probe_endpoints();
+                if let Some(rate_limiter) = &self.rate_limiter {
+                    #[cfg(feature = "tracing")]
+                    let waiting = std::time::Instant::now();
+                    rate_limiter.acquire(path).await;
+                    trace_record!("throttled_ms" = waiting.elapsed().as_millis() as u64);
+                }
+                let response = match self.http_client.execute(request).await {
+                    Ok(response) => response,
+                    Err(e) => {
+                        self.endpoints.observe(endpoint, false);
+                        return Err(e.into());
+                    }
+                };
+                self.endpoints
+                    .observe(endpoint, !response.status().is_server_error());
+                if let Some(rate_limiter) = &self.rate_limiter {
+                    rate_limiter.observe(response.headers());
+                }
+                let status = response.status();
+                let retry_after = retry_after(response.headers());
+                let body = response.text().await?;
+                self.record_http(&url, status, &body);
+                Ok((status, body, retry_after))
+            }
+        }
+    }
+
+    /// Probes the endpoints preferred to the active one in the background when they are due, to
+    /// fail back to them.
+    pub(crate) fn probe_endpoints(&self) {
+        let Some(endpoints) = self.endpoints.start_probe() else {
+            return;
+        };
+        let client = self.clone();
+        tokio::spawn(async move {
+            let timeout = client.endpoints.config.probe_timeout;
+            let mut results = Vec::with_capacity(endpoints.len());
+            for (index, endpoint) in endpoints {
+                let (health, _) = client.check_health(&endpoint.http_url, timeout).await;
+                results.push((index, health.is_healthy()));
+            }
+            client.endpoints.end_probe(results);
+        });
+    }
 
-        (self
+    fn record_http(&self, url: &str, status: StatusCode, body: &str) {
+        if let Some(recorder) = &self.recorder {
+            recorder.record(&CassetteEntry::Http {
+                at: Timestamp::now(),
+                method: "GET".to_string(),
+                url: url.to_string(),
+                status: status.as_u16(),
+                body: body.to_string(),
+            });
+        }
+    }
+
+    /// Checks the health of the Pragma API with a request to `/node` on the active endpoint,
+    /// within the timeout of [`crate::Config::with_health_timeout`].
+    ///
+    /// Failures are reported in the returned `Health` rather than as errors.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "pragma_http_request",
+            level = "debug",
+            skip_all,
+            fields(
+                endpoint = "is_healthy",
+                url = "/node",
+                status = tracing::field::Empty,
+                latency_ms = tracing::field::Empty,
+                replayed = self.replayer.is_some(),
+            )
+        )
+    )]
+    pub async fn health(&self) -> Health {
+        if let Some(replayer) = &self.replayer {
+            let response = replayer.http("GET", "/node");
+            return Health {
+                url: "/node".to_string(),
+                reachable: response.is_ok(),
+                status: response.as_ref().ok().map(|(status, _)| *status),
+                latency: Duration::ZERO,
+                version: None,
+                error: match response {
+                    Ok((status, _)) if (200..300).contains(&status) => None,
+                    Ok((status, body)) => Some(format!("API returned status {status}: {body}")),
+                    Err(e) => Some(e.to_string()),
+                },
+            };
+        }
+
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire("/node").await;
+        }
+        let (_, Endpoint { http_url, .. }) = self.endpoints.active();
+        let (health, response) = self
+            .check_health(http_url, self.config.health_timeout)
+            .await;
+
+        let status = response.as_ref().map(|(status, _, _)| *status);
+        telemetry::http_request("/node", status, health.latency);
+        trace_record!(
+            "latency_ms" = health.latency.as_millis() as u64,
+            "status" = health.status,
+        );
+        if !health.is_healthy() {
+            trace_event!(warn, error = health.error.as_deref(), "health check failed");
+        }
+        if let Some((status, headers, body)) = response {
+            if let Some(rate_limiter) = &self.rate_limiter {
+                rate_limiter.observe(&headers);
+            }
+            self.record_http("/node", status, &body);
+        }
+        health
+    }
+
+    /// Sends a request to the `/node` endpoint of `http_url`, and returns its health along with
+    /// the status, headers and body of the response, if any.
+    async fn check_health(
+        &self,
+        http_url: &str,
+        timeout: Duration,
+    ) -> (Health, Option<(StatusCode, HeaderMap, String)>) {
+        let url = format!("{http_url}/node");
+        let mut health = Health {
+            url: url.clone(),
+            reachable: false,
+            status: None,
+            latency: Duration::ZERO,
+            version: None,
+            error: None,
+        };
+        let api_key = match self.config.api_key.get().header_value() {
+            Ok(api_key) => api_key,
+            Err(e) => {
+                health.error = Some(e.to_string());
+                return (health, None);
+            }
+        };
+
+        let started = std::time::Instant::now();
+        let request = self
             .http_client
             .get(url)
-            .timeout(std::time::Duration::from_secs(2))
-            .send()
-            .await)
-            .map_or(false, |response| response.status().is_success())
+            .header("x-api-key", api_key)
+            .timeout(timeout)
+            .send();
+        let response = match request.await {
+            Ok(response) => response,
+            Err(e) => {
+                health.latency = started.elapsed();
+                health.error = Some(PragmaError::from(e).to_string());
+                return (health, None);
+            }
+        };
+        health.latency = started.elapsed();
+
+        let status = response.status();
+        let headers = response.headers().clone();
+        health.reachable = true;
+        health.status = Some(status.as_u16());
+        health.version = headers
+            .get(VERSION_HEADER)
+            .and_then(|version| version.to_str().ok())
+            .map(str::to_string);
+        let body = tokio::time::timeout(timeout, response.text()).await;
+        let body = body.ok().and_then(Result::ok).unwrap_or_default();
+        if !status.is_success() {
+            health.error = Some(format!("API returned status {status}: {body}"));
+        }
+        (health, Some((status, headers, body)))
+    }
+
+    /// Checks if the Pragma API is available, see [`PragmaClient::health`].
+    /// Returns true if the API responds successfully, false otherwise.
+    pub async fn is_healthy(&self) -> bool {
+        self.health().await.is_healthy()
+    }
+
+    /// Checks the health of the API every `interval` in a background task, and publishes the
+    /// changes of its state. The first check is done before returning.
+    ///
+    /// Fails with `PragmaError::InvalidConfig` if `interval` is zero, before checking anything.
+    pub async fn watch_health(&self, interval: Duration) -> Result<HealthWatcher, PragmaError> {
+        HealthWatcher::start(self.clone(), interval).await
     }
 
     #[cfg(feature = "sync")]
     pub fn is_healthy_sync(&self) -> bool {
-        let runtime = Self::runtime();
-        runtime.block_on(self.is_healthy())
+        Self::block_on(self.is_healthy())
+    }
+
+    #[cfg(feature = "sync")]
+    pub fn health_sync(&self) -> Health {
+        Self::block_on(self.health())
     }
 }
@@ -0,0 +1,257 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{telemetry, PragmaClient, PragmaError};
+
+use super::GetEntryResponse;
+
+/// Configuration of the `get_entry` cache, see [`crate::Config::with_entry_cache`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntryCacheConfig {
+    /// How long a response is served without contacting the API.
+    pub ttl: Duration,
+
+    /// How long after `ttl` an expired response is still served, while it is refreshed in the background.
+    pub stale_while_revalidate: Duration,
+}
+
+impl Default for EntryCacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(1),
+            stale_while_revalidate: Duration::ZERO,
+        }
+    }
+}
+
+/// Counters of the `get_entry` cache.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EntryCacheStats {
+    /// Requests answered with a fresh cached response, including the ones that waited for an in-flight request.
+    pub hits: u64,
+
+    /// Requests answered with an expired response while it was being refreshed.
+    pub stale_hits: u64,
+
+    /// Requests sent to the API.
+    pub misses: u64,
+
+    /// The number of `(pair, params)` keys currently cached.
+    pub entries: usize,
+}
+
+enum Lookup {
+    Fresh(GetEntryResponse),
+    Stale(GetEntryResponse),
+    Missing,
+}
+
+/// The cached response of one `(pair, params)` key.
+#[derive(Default)]
+struct Slot {
+    pair: String,
+    value: Mutex<Option<(GetEntryResponse, Instant)>>,
+    /// Held while a request is in flight for this key, so concurrent callers share its result.
+    fetch: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl Slot {
+    fn value(&self) -> MutexGuard<'_, Option<(GetEntryResponse, Instant)>> {
+        self.value.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn lookup(&self, config: &EntryCacheConfig) -> Lookup {
+        match &*self.value() {
+            Some((response, at)) if at.elapsed() <= config.ttl => Lookup::Fresh(response.clone()),
+            Some((response, at)) if at.elapsed() <= config.ttl + config.stale_while_revalidate => {
+                Lookup::Stale(response.clone())
+            }
+            _ => Lookup::Missing,
+        }
+    }
+
+    fn store(&self, response: GetEntryResponse) {
+        *self.value() = Some((response, Instant::now()));
+    }
+
+    /// Returns true if the slot can no longer serve its response, not even as a stale one.
+    fn expired(&self, config: &EntryCacheConfig) -> bool {
+        self.value().as_ref().map_or(true, |(_, at)| {
+            at.elapsed() > config.ttl + config.stale_while_revalidate
+        })
+    }
+}
+
+/// The slots of the cache, and when the expired ones were last removed.
+struct Slots {
+    slots: HashMap<String, Arc<Slot>>,
+    swept_at: Instant,
+}
+
+/// Cache of `get_entry` responses, shared by the clones of a `PragmaClient`.
+pub(crate) struct EntryCache {
+    config: EntryCacheConfig,
+    slots: Mutex<Slots>,
+    hits: AtomicU64,
+    stale_hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl std::fmt::Debug for EntryCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EntryCache")
+            .field("config", &self.config)
+            .field("stats", &self.stats())
+            .finish_non_exhaustive()
+    }
+}
+
+impl EntryCache {
+    pub(crate) fn new(config: EntryCacheConfig) -> Self {
+        Self {
+            config,
+            slots: Mutex::new(Slots {
+                slots: HashMap::new(),
+                swept_at: Instant::now(),
+            }),
+            hits: AtomicU64::new(0),
+            stale_hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    fn slots(&self) -> MutexGuard<'_, Slots> {
+        self.slots.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns the slot of `key`, creating it if needed.
+    ///
+    /// Every distinct set of parameters (e.g. each `timestamp`) gets its own slot, so before creating
+    /// one, the slots which expired and are not used by any request are removed, at most once per
+    /// `ttl + stale_while_revalidate`.
+    fn slot(&self, key: String, pair: &str) -> Arc<Slot> {
+        let mut slots = self.slots();
+        if let Some(slot) = slots.slots.get(&key) {
+            return slot.clone();
+        }
+
+        let lifetime = self.config.ttl + self.config.stale_while_revalidate;
+        if slots.swept_at.elapsed() >= lifetime {
+            // A slot referenced elsewhere is being fetched or waited on.
+            slots
+                .slots
+                .retain(|_, slot| Arc::strong_count(slot) > 1 || !slot.expired(&self.config));
+            slots.swept_at = Instant::now();
+        }
+
+        let slot = Arc::new(Slot {
+            pair: pair.to_uppercase(),
+            ..Default::default()
+        });
+        slots.slots.insert(key, slot.clone());
+        slot
+    }
+
+    pub(crate) fn stats(&self) -> EntryCacheStats {
+        EntryCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            stale_hits: self.stale_hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.slots().slots.len(),
+        }
+    }
+
+    /// Serves the request at `path` with `query` from the cache, fetching it with `client` if needed.
+    pub(crate) async fn get(
+        &self,
+        client: &PragmaClient,
+        pair: String,
+        path: String,
+        query: Vec<(&'static str, String)>,
+    ) -> Result<GetEntryResponse, PragmaError> {
+        let key = std::iter::once(path.clone())
+            .chain(query.iter().map(|(k, v)| format!("{k}={v}")))
+            .collect::<Vec<_>>()
+            .join("&")
+            .to_uppercase();
+        let slot = self.slot(key, &pair);
+
+        match slot.lookup(&self.config) {
+            Lookup::Fresh(response) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                telemetry::entry_cache("hit");
+                return Ok(response);
+            }
+            Lookup::Stale(response) => {
+                self.stale_hits.fetch_add(1, Ordering::Relaxed);
+                telemetry::entry_cache("stale_hit");
+                // Only one refresh per key: skip it if a request is already in flight.
+                if let Ok(guard) = slot.fetch.clone().try_lock_owned() {
+                    let client = client.clone();
+                    let slot = slot.clone();
+                    self.misses.fetch_add(1, Ordering::Relaxed);
+                    telemetry::entry_cache("miss");
+                    tokio::spawn(async move {
+                        let _guard = guard;
+                        if let Ok(response) = client.get_json(&path, &query).await {
+                            slot.store(response);
+                        }
+                    });
+                }
+                return Ok(response);
+            }
+            Lookup::Missing => {}
+        }
+
+        let _guard = slot.fetch.lock().await;
+        // Another caller may have fetched the response while we were waiting.
+        if let Lookup::Fresh(response) = slot.lookup(&self.config) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            telemetry::entry_cache("hit");
+            return Ok(response);
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        telemetry::entry_cache("miss");
+        let response: GetEntryResponse = client.get_json(&path, &query).await?;
+        slot.store(response.clone());
+        Ok(response)
+    }
+
+    /// Drops the cached responses of `pair` (e.g. "BTC/USD"), whatever their parameters.
+    pub(crate) fn invalidate(&self, pair: &str) {
+        let pair = pair.to_uppercase();
+        self.slots().slots.retain(|_, slot| slot.pair != pair);
+    }
+
+    pub(crate) fn clear(&self) {
+        self.slots().slots.clear();
+    }
+}
+
+impl PragmaClient {
+    /// Drops the cached `get_entry` responses of a trading pair. Does nothing if the cache is disabled.
+    pub fn invalidate_entry(&self, base: &str, quote: &str) {
+        if let Some(cache) = &self.entry_cache {
+            cache.invalidate(&format!("{base}/{quote}"));
+        }
+    }
+
+    /// Drops every cached `get_entry` response. Does nothing if the cache is disabled.
+    pub fn clear_entry_cache(&self) {
+        if let Some(cache) = &self.entry_cache {
+            cache.clear();
+        }
+    }
+
+    /// Returns the counters of the `get_entry` cache, or `None` if it is disabled.
+    pub fn entry_cache_stats(&self) -> Option<EntryCacheStats> {
+        self.entry_cache.as_ref().map(|cache| cache.stats())
+    }
+}
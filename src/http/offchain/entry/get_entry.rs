@@ -2,20 +2,22 @@ use std::num::ParseIntError;
 
 #[cfg(feature = "bigdecimal")]
 use bigdecimal::BigDecimal;
+use std::time::Duration;
+
 use pragma_common::{
     aggregation::AggregationMode, instrument_type::InstrumentType, interval::Interval,
 };
 use serde::{Deserialize, Serialize};
 
-use crate::{client::PragmaClient, PragmaError};
+use crate::{client::PragmaClient, PragmaError, Timestamp};
 
 /// Optional query parameters for the "Data Pair" endpoint.
 ///
 /// This struct defines the parameters that can be passed to customize data retrieval.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct GetEntryParams {
-    /// The timestamp for which to retrieve data, in milliseconds since the Unix epoch.
-    pub timestamp: Option<u64>,
+    /// The timestamp for which to retrieve data.
+    pub timestamp: Option<Timestamp>,
 
     /// The time interval over which to aggregate data.
     pub interval: Option<Interval>,
@@ -31,12 +33,16 @@ pub struct GetEntryParams {
 
     /// Whether to include component data in the response.
     pub with_components: Option<bool>,
+
+    /// Fails with `PragmaError::StalePrice` if the price is older than this, see [`crate::Config::with_clock`].
+    /// Not sent to the API.
+    pub max_age: Option<Duration>,
 }
 
 /// Individual price component from a source.
 ///
 /// Represents a single price contribution from a specific source.
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Component {
     /// The price value as a string.
     pub price: String,
@@ -44,14 +50,14 @@ pub struct Component {
     /// The source of the price data.
     pub source: String,
 
-    /// The timestamp of the price data, in milliseconds since the Unix epoch.
-    pub timestamp: u64,
+    /// The timestamp of the price data.
+    pub timestamp: Timestamp,
 }
 
 /// Response for the "Data Pair" offchain endpoint.
 ///
 /// Contains the aggregated price data and optional components for a trading pair.
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct GetEntryResponse {
     /// The number of decimal places in the price.
     pub decimals: u32,
@@ -65,8 +71,8 @@ pub struct GetEntryResponse {
     /// The aggregated price as a string.
     pub price: String,
 
-    /// The timestamp of the price data, in milliseconds since the Unix epoch.
-    pub timestamp: u64,
+    /// The timestamp of the price data.
+    pub timestamp: Timestamp,
 
     /// Optional list of individual price components, included if `with_components` is true.
     #[serde(default)]
@@ -78,6 +84,11 @@ impl GetEntryResponse {
         u128::from_str_radix(&self.price.replace("0x", ""), 16)
     }
 
+    /// Returns the price scaled by `decimals`, e.g. `0x5f5e100` with 8 decimals is `1.0`.
+    pub fn price_f64(&self) -> Result<f64, ParseIntError> {
+        Ok(self.price_u128()? as f64 / 10f64.powi(self.decimals as i32))
+    }
+
     #[cfg(feature = "bigdecimal")]
     pub fn price_bd(&self) -> Result<BigDecimal, ParseIntError> {
         let price_u128 = u128::from_str_radix(&self.price.replace("0x", ""), 16)?;
@@ -91,6 +102,9 @@ impl PragmaClient {
     /// This method retrieves price data for a specified base and quote asset pair, with optional parameters to customize
     /// the aggregation and filtering of the data.
     ///
+    /// When the entry cache is enabled with [`crate::Config::with_entry_cache`], responses are served from the cache
+    /// while they are fresh, and concurrent requests for the same pair and parameters share a single API call.
+    ///
     /// # Arguments
     ///
     /// * `base` - The base asset symbol (e.g., "BTC").
@@ -127,13 +141,11 @@ impl PragmaClient {
         quote: &str,
         params: Option<GetEntryParams>,
     ) -> Result<GetEntryResponse, PragmaError> {
-        let url = format!("{}/node/v1/data/{}/{}", self.config.base_url, base, quote);
-        let mut request = self.http_client.get(&url);
-
+        let mut query = Vec::new();
+        let max_age = params.as_ref().and_then(|p| p.max_age);
         if let Some(p) = params {
-            let mut query = Vec::new();
             if let Some(ts) = p.timestamp {
-                query.push(("timestamp", ts.to_string()));
+                query.push(("timestamp", ts.as_millis().to_string()));
             }
             if let Some(interval) = p.interval {
                 query.push(("interval", interval.as_str().to_string()));
@@ -150,35 +162,22 @@ impl PragmaClient {
             if let Some(wc) = p.with_components {
                 query.push(("with_components", wc.to_string()));
             }
-            request = request.query(&query);
         }
 
-        let response = request.send().await?;
-
-        match response.status() {
-            reqwest::StatusCode::UNAUTHORIZED => {
-                let text = response
-                    .text()
-                    .await
-                    .unwrap_or_else(|_| "No additional details".to_string());
-                return Err(PragmaError::Unauthorized(text));
+        let path = format!("/node/v1/data/{base}/{quote}");
+        let response: GetEntryResponse = match &self.entry_cache {
+            Some(cache) => {
+                cache
+                    .get(self, format!("{base}/{quote}"), path, query)
+                    .await?
             }
-            status if !status.is_success() => {
-                let text = response
-                    .text()
-                    .await
-                    .unwrap_or_else(|_| "Unknown error".to_string());
-                return Err(PragmaError::ApiError(format!(
-                    "API returned status {status}: {text}",
-                )));
-            }
-            _ => {}
-        }
+            None => self.get_json(&path, &query).await?,
+        };
 
-        response
-            .json::<GetEntryResponse>()
-            .await
-            .map_err(PragmaError::HttpError)
+        self.config
+            .clock
+            .check_freshness(response.timestamp, max_age)?;
+        Ok(response)
     }
 
     #[cfg(feature = "sync")]
@@ -188,7 +187,6 @@ impl PragmaClient {
         quote: &str,
         params: Option<GetEntryParams>,
     ) -> Result<GetEntryResponse, PragmaError> {
-        let runtime = Self::runtime();
-        runtime.block_on(self.get_entry(base, quote, params))
+        Self::block_on(self.get_entry(base, quote, params))
     }
 }
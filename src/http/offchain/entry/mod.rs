@@ -1,3 +1,5 @@
+pub mod cache;
 pub mod get_entry;
 
+pub use cache::{EntryCacheConfig, EntryCacheStats};
 pub use get_entry::{Component, GetEntryParams, GetEntryResponse};
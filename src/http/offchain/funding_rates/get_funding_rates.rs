@@ -1,6 +1,6 @@
-use crate::{PragmaClient, PragmaError};
+use crate::{PragmaClient, PragmaError, Timestamp};
 
-use super::FundingRatesEntry;
+use super::{FundingRatesEntry, FundingSource};
 
 pub type GetFundingRatesResponse = FundingRatesEntry;
 
@@ -14,7 +14,7 @@ impl PragmaClient {
     /// * `base` - The base asset symbol (e.g., "BTC").
     /// * `quote` - The quote asset symbol (e.g., "USD").
     /// * `source` - The source of the funding rate data.
-    /// * `timestamp_s` - Optional timestamp in seconds since the Unix epoch.
+    /// * `timestamp` - Optional timestamp, sent with a precision of one second.
     ///
     /// # Returns
     ///
@@ -23,13 +23,15 @@ impl PragmaClient {
     /// # Examples
     ///
     /// ```no_run
-    /// use pragma_rs::{Config, Environment, PragmaError, PragmaClient};
+    /// use pragma_rs::{Config, Environment, FundingSource, PragmaError, PragmaClient};
     ///
     /// #[tokio::main]
     /// async fn main() -> Result<(), PragmaError> {
     ///     let config = Config::new("your_api_key".to_string(), Environment::Development);
     ///     let client = PragmaClient::new(config)?;
-    ///     let response = client.get_funding_rates("BTC", "USD", "hyperliquid", None).await?;
+    ///     let response = client
+    ///         .get_funding_rates("BTC", "USD", &FundingSource::Hyperliquid, None)
+    ///         .await?;
     ///     println!("Funding Rate: {}", response.hourly_rate);
     ///     Ok(())
     /// }
@@ -38,45 +40,16 @@ impl PragmaClient {
         &self,
         base: &str,
         quote: &str,
-        source: &str,
-        timestamp_s: Option<u64>,
+        source: &FundingSource,
+        timestamp: Option<Timestamp>,
     ) -> Result<GetFundingRatesResponse, PragmaError> {
-        let url = format!(
-            "{}/node/v1/funding_rates/{}/{}",
-            self.config.base_url, base, quote
-        );
         let mut query = vec![("source", source.to_string())];
-        if let Some(timestamp_s) = timestamp_s {
-            query.push(("timestamp", timestamp_s.to_string()));
+        if let Some(timestamp) = timestamp {
+            query.push(("timestamp", timestamp.as_secs().to_string()));
         }
-        let request = self.http_client.get(&url).query(&query);
 
-        let response = request.send().await?;
-
-        match response.status() {
-            reqwest::StatusCode::UNAUTHORIZED => {
-                let text = response
-                    .text()
-                    .await
-                    .unwrap_or_else(|_| "No additional details".to_string());
-                return Err(PragmaError::Unauthorized(text));
-            }
-            status if !status.is_success() => {
-                let text = response
-                    .text()
-                    .await
-                    .unwrap_or_else(|_| "Unknown error".to_string());
-                return Err(PragmaError::ApiError(format!(
-                    "API returned status {status}: {text}",
-                )));
-            }
-            _ => {}
-        }
-
-        response
-            .json::<GetFundingRatesResponse>()
+        self.get_json(&format!("/node/v1/funding_rates/{base}/{quote}"), &query)
             .await
-            .map_err(PragmaError::HttpError)
     }
 
     #[cfg(feature = "sync")]
@@ -84,10 +57,9 @@ impl PragmaClient {
         &self,
         base: &str,
         quote: &str,
-        source: &str,
-        timestamp_s: Option<u64>,
+        source: &FundingSource,
+        timestamp: Option<Timestamp>,
     ) -> Result<GetFundingRatesResponse, PragmaError> {
-        let runtime = Self::runtime();
-        runtime.block_on(self.get_funding_rates(base, quote, source, timestamp_s))
+        Self::block_on(self.get_funding_rates(base, quote, source, timestamp))
     }
 }
@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+
+use futures_util::{stream, StreamExt};
+
+use crate::{PragmaClient, PragmaError, Timestamp};
+
+use super::{FundingRatesEntry, FundingSource};
+
+/// Maximum number of sources queried concurrently by `get_funding_rates_all_sources`.
+pub const DEFAULT_SOURCES_CONCURRENCY: usize = 4;
+
+/// Summary statistics of the hourly funding rates across sources.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FundingRatesStats {
+    /// The lowest hourly rate and the source publishing it.
+    pub min: (FundingSource, f64),
+
+    /// The highest hourly rate and the source publishing it.
+    pub max: (FundingSource, f64),
+
+    /// The mean hourly rate across sources.
+    pub mean: f64,
+
+    /// The difference between the highest and the lowest hourly rates.
+    pub spread: f64,
+}
+
+impl FundingRatesStats {
+    /// Computes the statistics of `rates`, or `None` if it is empty.
+    pub fn from_rates(rates: &BTreeMap<FundingSource, FundingRatesEntry>) -> Option<Self> {
+        let mut iter = rates.iter();
+        let (first_source, first) = iter.next()?;
+
+        let mut min = (first_source, first.hourly_rate);
+        let mut max = (first_source, first.hourly_rate);
+        let mut sum = first.hourly_rate;
+        for (source, entry) in iter {
+            if entry.hourly_rate < min.1 {
+                min = (source, entry.hourly_rate);
+            }
+            if entry.hourly_rate > max.1 {
+                max = (source, entry.hourly_rate);
+            }
+            sum += entry.hourly_rate;
+        }
+
+        Some(Self {
+            min: (min.0.clone(), min.1),
+            max: (max.0.clone(), max.1),
+            mean: sum / rates.len() as f64,
+            spread: max.1 - min.1,
+        })
+    }
+}
+
+/// Response from the `get_funding_rates_all_sources` method.
+#[derive(Debug)]
+pub struct GetFundingRatesAllSourcesResponse {
+    /// The funding rates of the pair, for each source listing it and answering successfully.
+    pub rates: BTreeMap<FundingSource, FundingRatesEntry>,
+
+    /// The error of each source listing the pair whose funding rates could not be fetched.
+    pub errors: BTreeMap<FundingSource, PragmaError>,
+
+    /// Statistics over `rates`, `None` if no source answered.
+    pub stats: Option<FundingRatesStats>,
+}
+
+impl PragmaClient {
+    /// Fetches the funding rates of a trading pair on every source listing it.
+    ///
+    /// The sources are discovered through the funding rate instruments endpoint, then queried concurrently,
+    /// [`DEFAULT_SOURCES_CONCURRENCY`] at a time. A source failing does not fail the whole snapshot: its error
+    /// is reported in `errors` instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `base` - The base asset symbol (e.g., "BTC").
+    /// * `quote` - The quote asset symbol (e.g., "USD").
+    /// * `timestamp` - Optional timestamp, sent with a precision of one second.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `GetFundingRatesAllSourcesResponse` on success, or a `PragmaError` if the sources
+    /// could not be discovered.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pragma_rs::{Config, Environment, PragmaError, PragmaClient};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), PragmaError> {
+    ///     let config = Config::new("your_api_key".to_string(), Environment::Development);
+    ///     let client = PragmaClient::new(config)?;
+    ///     let response = client.get_funding_rates_all_sources("BTC", "USD", None).await?;
+    ///     if let Some(stats) = response.stats {
+    ///         println!("Funding spread: {}", stats.spread);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn get_funding_rates_all_sources(
+        &self,
+        base: &str,
+        quote: &str,
+        timestamp: Option<Timestamp>,
+    ) -> Result<GetFundingRatesAllSourcesResponse, PragmaError> {
+        let pair = format!("{base}/{quote}");
+        let mut sources: Vec<FundingSource> = self
+            .fetch_funding_rate_instruments(None)
+            .await?
+            .into_iter()
+            .filter(|i| i.pair.eq_ignore_ascii_case(&pair))
+            .map(|i| i.source)
+            .collect();
+        sources.sort();
+        sources.dedup();
+
+        let mut responses = stream::iter(sources)
+            .map(|source| async move {
+                let response = self
+                    .get_funding_rates(base, quote, &source, timestamp)
+                    .await;
+                (source, response)
+            })
+            .buffer_unordered(DEFAULT_SOURCES_CONCURRENCY);
+
+        let mut rates = BTreeMap::new();
+        let mut errors = BTreeMap::new();
+        while let Some((source, response)) = responses.next().await {
+            match response {
+                Ok(entry) => {
+                    rates.insert(source, entry);
+                }
+                Err(e) => {
+                    errors.insert(source, e);
+                }
+            }
+        }
+        let stats = FundingRatesStats::from_rates(&rates);
+
+        Ok(GetFundingRatesAllSourcesResponse {
+            rates,
+            errors,
+            stats,
+        })
+    }
+
+    #[cfg(feature = "sync")]
+    pub fn get_funding_rates_all_sources_sync(
+        &self,
+        base: &str,
+        quote: &str,
+        timestamp: Option<Timestamp>,
+    ) -> Result<GetFundingRatesAllSourcesResponse, PragmaError> {
+        Self::block_on(self.get_funding_rates_all_sources(base, quote, timestamp))
+    }
+}
@@ -1,70 +1,185 @@
-use crate::{PragmaClient, PragmaError};
+use std::time::Duration;
 
-use super::FundingRatesEntry;
+use futures_util::{stream, Stream, StreamExt, TryStreamExt};
+
+use crate::{PragmaClient, PragmaError, Timestamp};
+
+use super::{FundingRatesEntry, FundingSource};
 
 pub type GetHistoricalFundingRatesResponse = Vec<FundingRatesEntry>;
 
+/// Default length of a single historical funding rates request window: one day.
+pub const DEFAULT_HISTORY_CHUNK: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Default number of historical funding rates windows fetched concurrently.
+pub const DEFAULT_HISTORY_CONCURRENCY: usize = 4;
+
+/// Controls how a historical funding rates range is split into requests.
+#[derive(Debug, Clone, Copy)]
+pub struct HistoryChunking {
+    /// The maximum length of a single request window.
+    pub chunk: Duration,
+
+    /// The maximum number of windows fetched concurrently.
+    pub max_concurrency: usize,
+}
+
+impl Default for HistoryChunking {
+    fn default() -> Self {
+        Self {
+            chunk: DEFAULT_HISTORY_CHUNK,
+            max_concurrency: DEFAULT_HISTORY_CONCURRENCY,
+        }
+    }
+}
+
+impl HistoryChunking {
+    /// Splits `[from_ts, to_ts]` into consecutive windows of at most `chunk`.
+    ///
+    /// Windows are generated lazily, so a small `chunk` over a long range only costs the requests
+    /// actually in flight. A reversed range has no window.
+    fn windows(
+        &self,
+        from_ts: Timestamp,
+        to_ts: Timestamp,
+    ) -> impl Iterator<Item = (Timestamp, Timestamp)> {
+        let chunk = self.chunk.max(Duration::from_millis(1));
+        let mut next = (from_ts <= to_ts).then_some(from_ts);
+        std::iter::from_fn(move || {
+            let start = next?;
+            let end = (start + chunk).min(to_ts);
+            next = (end < to_ts).then_some(end);
+            Some((start, end))
+        })
+    }
+}
+
 impl PragmaClient {
     /// Fetches historical funding rate data for a trading pair from the offchain "Historical Funding Rates" endpoint.
     ///
     /// This method retrieves historical funding rate data for a specified base and quote asset pair on a specific source.
+    /// The range is split into windows of [`DEFAULT_HISTORY_CHUNK`] fetched concurrently, so large ranges are not
+    /// truncated by the server. See [`PragmaClient::get_historical_funding_rates_chunked`] to tune the chunking.
     ///
     /// # Arguments
     ///
     /// * `base` - The base asset symbol (e.g., "BTC").
     /// * `quote` - The quote asset symbol (e.g., "USD").
-    /// * `from_ts` - The start timestamp in milliseconds since the Unix epoch.
-    /// * `to_ts` - The end timestamp in milliseconds since the Unix epoch.
+    /// * `from_ts` - The start timestamp.
+    /// * `to_ts` - The end timestamp.
     /// * `source` - The source of the funding rate data.
     ///
     /// # Returns
     ///
-    /// A `Result` containing the `GetHistoricalFundingRatesResponse` on success, or a `PragmaError` on failure.
-    /// ```
+    /// A `Result` containing the `GetHistoricalFundingRatesResponse`, deduplicated and sorted by `timestamp`, on
+    /// success, or a `PragmaError` on failure. The response is empty if `from_ts` is after `to_ts`.
     pub async fn get_historical_funding_rates(
         &self,
         base: &str,
         quote: &str,
-        from_ts: u128,
-        to_ts: u128,
-        source: &str,
+        from_ts: Timestamp,
+        to_ts: Timestamp,
+        source: &FundingSource,
+    ) -> Result<GetHistoricalFundingRatesResponse, PragmaError> {
+        self.get_historical_funding_rates_chunked(
+            base,
+            quote,
+            from_ts,
+            to_ts,
+            source,
+            HistoryChunking::default(),
+        )
+        .await
+    }
+
+    /// Same as [`PragmaClient::get_historical_funding_rates`], with a custom `chunking`.
+    pub async fn get_historical_funding_rates_chunked(
+        &self,
+        base: &str,
+        quote: &str,
+        from_ts: Timestamp,
+        to_ts: Timestamp,
+        source: &FundingSource,
+        chunking: HistoryChunking,
+    ) -> Result<GetHistoricalFundingRatesResponse, PragmaError> {
+        let mut entries: GetHistoricalFundingRatesResponse = self
+            .stream_historical_funding_rates(base, quote, from_ts, to_ts, source, chunking)
+            .try_concat()
+            .await?;
+
+        entries.sort_by_key(|entry| entry.timestamp);
+        entries.dedup_by_key(|entry| entry.timestamp);
+        Ok(entries)
+    }
+
+    /// Streams historical funding rate data for a trading pair, one page per window.
+    ///
+    /// Pages are yielded as soon as they arrive, so they are not ordered and may overlap on their boundaries.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use futures_util::StreamExt;
+    /// use pragma_rs::{
+    ///     Config, Environment, FundingSource, HistoryChunking, PragmaError, PragmaClient, Timestamp,
+    /// };
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), PragmaError> {
+    ///     let config = Config::new("your_api_key".to_string(), Environment::Development);
+    ///     let client = PragmaClient::new(config)?;
+    ///     let source = FundingSource::Hyperliquid;
+    ///     let mut pages = Box::pin(client.stream_historical_funding_rates(
+    ///         "BTC",
+    ///         "USD",
+    ///         Timestamp::from_secs(1746448809),
+    ///         Timestamp::from_secs(1746535238),
+    ///         &source,
+    ///         HistoryChunking::default(),
+    ///     ));
+    ///     while let Some(page) = pages.next().await {
+    ///         println!("Received {} funding rates", page?.len());
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn stream_historical_funding_rates<'a>(
+        &'a self,
+        base: &'a str,
+        quote: &'a str,
+        from_ts: Timestamp,
+        to_ts: Timestamp,
+        source: &'a FundingSource,
+        chunking: HistoryChunking,
+    ) -> impl Stream<Item = Result<GetHistoricalFundingRatesResponse, PragmaError>> + 'a {
+        stream::iter(chunking.windows(from_ts, to_ts))
+            .map(move |(from_ts, to_ts)| {
+                self.fetch_historical_funding_rates_window(base, quote, from_ts, to_ts, source)
+            })
+            .buffer_unordered(chunking.max_concurrency.max(1))
+    }
+
+    async fn fetch_historical_funding_rates_window(
+        &self,
+        base: &str,
+        quote: &str,
+        from_ts: Timestamp,
+        to_ts: Timestamp,
+        source: &FundingSource,
     ) -> Result<GetHistoricalFundingRatesResponse, PragmaError> {
-        let url = format!(
-            "{}/node/v1/funding_rates/history/{}/{}",
-            self.config.base_url, base, quote
-        );
         let query = vec![
-            ("timestamp", format!("{},{}", from_ts, to_ts)),
+            (
+                "timestamp",
+                format!("{},{}", from_ts.as_millis(), to_ts.as_millis()),
+            ),
             ("source", source.to_string()),
         ];
-        let request = self.http_client.get(&url).query(&query);
-
-        let response = request.send().await?;
-
-        match response.status() {
-            reqwest::StatusCode::UNAUTHORIZED => {
-                let text = response
-                    .text()
-                    .await
-                    .unwrap_or_else(|_| "No additional details".to_string());
-                return Err(PragmaError::Unauthorized(text));
-            }
-            status if !status.is_success() => {
-                let text = response
-                    .text()
-                    .await
-                    .unwrap_or_else(|_| "Unknown error".to_string());
-                return Err(PragmaError::ApiError(format!(
-                    "API returned status {status}: {text}",
-                )));
-            }
-            _ => {}
-        }
 
-        response
-            .json::<GetHistoricalFundingRatesResponse>()
-            .await
-            .map_err(PragmaError::HttpError)
+        self.get_json(
+            &format!("/node/v1/funding_rates/history/{base}/{quote}"),
+            &query,
+        )
+        .await
     }
 
     #[cfg(feature = "sync")]
@@ -72,11 +187,10 @@ impl PragmaClient {
         &self,
         base: &str,
         quote: &str,
-        from_ts: u128,
-        to_ts: u128,
-        source: &str,
+        from_ts: Timestamp,
+        to_ts: Timestamp,
+        source: &FundingSource,
     ) -> Result<GetHistoricalFundingRatesResponse, PragmaError> {
-        let runtime = Self::runtime();
-        runtime.block_on(self.get_historical_funding_rates(base, quote, from_ts, to_ts, source))
+        Self::block_on(self.get_historical_funding_rates(base, quote, from_ts, to_ts, source))
     }
 }
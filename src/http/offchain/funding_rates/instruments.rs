@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{PragmaClient, PragmaError};
+
+use crate::Timestamp;
+
+use super::FundingSource;
+
+/// An instrument for which funding rates are available.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FundingRateInstrument {
+    /// The source publishing the funding rates.
+    pub source: FundingSource,
+
+    /// The identifier of the trading pair (e.g., "BTC/USD").
+    pub pair: String,
+
+    /// The timestamp of the first funding rate available.
+    #[serde(rename = "first_timestamp_ms")]
+    pub first_timestamp: Timestamp,
+
+    /// The timestamp of the last funding rate available.
+    #[serde(rename = "last_timestamp_ms")]
+    pub last_timestamp: Timestamp,
+}
+
+pub type ListFundingRateInstrumentsResponse = Vec<FundingRateInstrument>;
+
+impl PragmaClient {
+    /// Lists the sources for which funding rates are available.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the sorted and deduplicated sources on success, or a `PragmaError` on failure.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pragma_rs::{Config, Environment, PragmaError, PragmaClient};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), PragmaError> {
+    ///     let config = Config::new("your_api_key".to_string(), Environment::Development);
+    ///     let client = PragmaClient::new(config)?;
+    ///     for source in client.list_funding_rate_sources().await? {
+    ///         println!("{source}");
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn list_funding_rate_sources(&self) -> Result<Vec<FundingSource>, PragmaError> {
+        let instruments = self.fetch_funding_rate_instruments(None).await?;
+
+        let mut sources: Vec<FundingSource> = instruments.into_iter().map(|i| i.source).collect();
+        sources.sort();
+        sources.dedup();
+        Ok(sources)
+    }
+
+    /// Lists the instruments for which funding rates are available on `source`.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source of the funding rate data.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `ListFundingRateInstrumentsResponse` on success, or a `PragmaError` on failure.
+    pub async fn list_funding_rate_instruments(
+        &self,
+        source: &FundingSource,
+    ) -> Result<ListFundingRateInstrumentsResponse, PragmaError> {
+        let instruments = self.fetch_funding_rate_instruments(Some(source)).await?;
+        Ok(instruments
+            .into_iter()
+            .filter(|i| &i.source == source)
+            .collect())
+    }
+
+    pub(crate) async fn fetch_funding_rate_instruments(
+        &self,
+        source: Option<&FundingSource>,
+    ) -> Result<ListFundingRateInstrumentsResponse, PragmaError> {
+        let mut query = Vec::new();
+        if let Some(source) = source {
+            query.push(("source", source.to_string()));
+        }
+
+        self.get_json("/node/v1/funding_rates/instruments", &query)
+            .await
+    }
+
+    #[cfg(feature = "sync")]
+    pub fn list_funding_rate_sources_sync(&self) -> Result<Vec<FundingSource>, PragmaError> {
+        Self::block_on(self.list_funding_rate_sources())
+    }
+
+    #[cfg(feature = "sync")]
+    pub fn list_funding_rate_instruments_sync(
+        &self,
+        source: &FundingSource,
+    ) -> Result<ListFundingRateInstrumentsResponse, PragmaError> {
+        Self::block_on(self.list_funding_rate_instruments(source))
+    }
+}
@@ -1,15 +1,28 @@
 use serde::{Deserialize, Serialize};
 
+use crate::Timestamp;
+
 pub mod get_funding_rates;
+pub mod get_funding_rates_all_sources;
 pub mod get_historical_funding_rates;
+pub mod instruments;
+pub mod source;
 
 pub use get_funding_rates::GetFundingRatesResponse;
-pub use get_historical_funding_rates::GetHistoricalFundingRatesResponse;
+pub use get_funding_rates_all_sources::{
+    FundingRatesStats, GetFundingRatesAllSourcesResponse, DEFAULT_SOURCES_CONCURRENCY,
+};
+pub use get_historical_funding_rates::{
+    GetHistoricalFundingRatesResponse, HistoryChunking, DEFAULT_HISTORY_CHUNK,
+    DEFAULT_HISTORY_CONCURRENCY,
+};
+pub use instruments::{FundingRateInstrument, ListFundingRateInstrumentsResponse};
+pub use source::FundingSource;
 
 // Response for the "Historical Funding Rates" offchain endpoint.
 ///
 /// Contains the historical funding rate data for a trading pair on a specific source.
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct FundingRatesEntry {
     /// The hourly funding rate as a percentage.
     pub hourly_rate: f64,
@@ -20,6 +33,7 @@ pub struct FundingRatesEntry {
     /// The source of the funding rate data.
     pub source: String,
 
-    /// The timestamp of the funding rate data, in milliseconds since the Unix epoch.
-    pub timestamp_ms: u64,
+    /// The timestamp of the funding rate data.
+    #[serde(rename = "timestamp_ms")]
+    pub timestamp: Timestamp,
 }
@@ -0,0 +1,157 @@
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
+
+use pragma_common::Exchange;
+use serde::{Deserialize, Serialize};
+
+use crate::PragmaError;
+
+/// A venue publishing funding rates through the Pragma API.
+///
+/// Parsing with [`FromStr`] only accepts known sources, so a typo is caught before any request is
+/// sent. Sources that are not listed yet can still be targeted explicitly with
+/// [`FundingSource::other`], which maps known names back to their own variant.
+///
+/// Sources compare by name, ignoring ASCII case, so a known source spelled through
+/// [`FundingSource::Other`] is still equal to its variant.
+///
+/// # Examples
+///
+/// ```
+/// use pragma_rs::FundingSource;
+///
+/// let source: FundingSource = "hyperliquid".parse().unwrap();
+/// assert_eq!(source, FundingSource::Hyperliquid);
+/// assert!("hyperliqiud".parse::<FundingSource>().is_err());
+/// assert_eq!(FundingSource::other("Hyperliquid"), FundingSource::Hyperliquid);
+/// assert_eq!(FundingSource::Other("paradex".into()), FundingSource::Paradex);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum FundingSource {
+    Hyperliquid,
+    Paradex,
+    Extended,
+    Kraken,
+    /// Any source not (yet) known by the SDK, sent as-is. Prefer [`FundingSource::other`] to
+    /// build it.
+    Other(String),
+}
+
+impl FundingSource {
+    /// Every source known by the SDK, excluding [`FundingSource::Other`].
+    pub const KNOWN: [Self; 4] = [
+        Self::Hyperliquid,
+        Self::Paradex,
+        Self::Extended,
+        Self::Kraken,
+    ];
+
+    /// Builds a source from its name, returning the known variant when there is one.
+    pub fn other(source: impl Into<String>) -> Self {
+        Self::from(source.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Hyperliquid => "hyperliquid",
+            Self::Paradex => "paradex",
+            Self::Extended => "extended",
+            Self::Kraken => "kraken",
+            Self::Other(source) => source,
+        }
+    }
+
+    /// Position of the source in [`FundingSource::KNOWN`], unknown sources sorting last.
+    fn rank(&self) -> usize {
+        Self::KNOWN
+            .iter()
+            .position(|known| known.as_str().eq_ignore_ascii_case(self.as_str()))
+            .unwrap_or(Self::KNOWN.len())
+    }
+}
+
+impl PartialEq for FundingSource {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str().eq_ignore_ascii_case(other.as_str())
+    }
+}
+
+impl Eq for FundingSource {}
+
+impl Hash for FundingSource {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for byte in self.as_str().bytes() {
+            state.write_u8(byte.to_ascii_lowercase());
+        }
+        state.write_u8(0xff);
+    }
+}
+
+impl PartialOrd for FundingSource {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Known sources keep their declaration order, unknown ones follow sorted by name.
+impl Ord for FundingSource {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let lowercase = |source: &Self| {
+            source
+                .as_str()
+                .bytes()
+                .map(|byte| byte.to_ascii_lowercase())
+                .collect::<Vec<_>>()
+        };
+        self.rank()
+            .cmp(&other.rank())
+            .then_with(|| lowercase(self).cmp(&lowercase(other)))
+    }
+}
+
+impl fmt::Display for FundingSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for FundingSource {
+    type Err = PragmaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::KNOWN
+            .into_iter()
+            .find(|source| source.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| PragmaError::UnknownFundingSource(s.to_string()))
+    }
+}
+
+/// Lenient conversion used when decoding API responses: unknown sources become `Other`.
+impl From<String> for FundingSource {
+    fn from(s: String) -> Self {
+        s.parse().unwrap_or(Self::Other(s))
+    }
+}
+
+impl From<FundingSource> for String {
+    fn from(source: FundingSource) -> Self {
+        source.as_str().to_string()
+    }
+}
+
+impl From<Exchange> for FundingSource {
+    fn from(exchange: Exchange) -> Self {
+        match exchange {
+            Exchange::Hyperliquid => Self::Hyperliquid,
+            Exchange::Paradex => Self::Paradex,
+            Exchange::Extended => Self::Extended,
+            Exchange::Kraken => Self::Kraken,
+            other => Self::other(other.to_string().to_lowercase()),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::num::ParseIntError;
+use std::{num::ParseIntError, time::Duration};
 
 #[cfg(feature = "bigdecimal")]
 use bigdecimal::BigDecimal;
@@ -6,10 +6,10 @@ use serde::{Deserialize, Serialize};
 
 use pragma_common::{aggregation::AggregationMode, starknet::StarknetNetwork};
 
-use crate::{PragmaClient, PragmaError};
+use crate::{PragmaClient, PragmaError, Timestamp};
 
 /// Parameters for the `get_onchain_entry` method.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct GetOnchainEntryParams {
     /// The network to query (required).
     pub network: StarknetNetwork,
@@ -18,12 +18,14 @@ pub struct GetOnchainEntryParams {
     /// Optional routing flag.
     pub routing: Option<bool>,
     /// Optional timestamp filter.
-    pub timestamp: Option<u64>,
+    pub timestamp: Option<Timestamp>,
     /// Whether to include components in the response.
     pub components: Option<bool>,
+    /// Fails with `PragmaError::StalePrice` if the last update is older than this. Not sent to the API.
+    pub max_age: Option<Duration>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct OnchainComponent {
     /// The price from this component.
     pub price: String,
@@ -31,21 +33,23 @@ pub struct OnchainComponent {
     pub publisher: String,
     /// The source of the data.
     pub source: String,
-    /// The timestamp of the data.
-    pub timestamp: u64,
+    /// The timestamp of the data, encoded in seconds.
+    #[serde(with = "crate::timestamp::seconds")]
+    pub timestamp: Timestamp,
     /// The transaction hash.
     pub tx_hash: String,
 }
 
 /// Response from the `get_onchain_entry` method.
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct GetOnchainEntryResponse {
     /// The type of the asset.
     pub asset_type: String,
     /// The number of decimal places for the price.
     pub decimals: u32,
-    /// The timestamp of the last update.
-    pub last_updated_timestamp: u64,
+    /// The timestamp of the last update, encoded in seconds.
+    #[serde(with = "crate::timestamp::seconds")]
+    pub last_updated_timestamp: Timestamp,
     /// The number of sources aggregated.
     pub nb_sources_aggregated: u32,
     /// The identifier of the trading pair.
@@ -62,6 +66,11 @@ impl GetOnchainEntryResponse {
         u128::from_str_radix(&self.price.replace("0x", ""), 16)
     }
 
+    /// Returns the price scaled by `decimals`, e.g. `0x5f5e100` with 8 decimals is `1.0`.
+    pub fn price_f64(&self) -> Result<f64, ParseIntError> {
+        Ok(self.price_u128()? as f64 / 10f64.powi(self.decimals as i32))
+    }
+
     #[cfg(feature = "bigdecimal")]
     pub fn price_bd(&self) -> Result<BigDecimal, ParseIntError> {
         let price_u128 = u128::from_str_radix(&self.price.replace("0x", ""), 16)?;
@@ -97,6 +106,7 @@ impl PragmaClient {
     ///         components: Some(true),
     ///         routing: None,
     ///         timestamp: None,
+    ///         max_age: None,
     ///     };
     ///     let response = client.get_onchain_entry("BTC", "USD", params).await?;
     ///     println!("Price: {}", response.price);
@@ -109,12 +119,6 @@ impl PragmaClient {
         quote: &str,
         params: GetOnchainEntryParams,
     ) -> Result<GetOnchainEntryResponse, PragmaError> {
-        let url = format!(
-            "{}/node/v1/onchain/{}/{}",
-            self.config.base_url, base, quote
-        );
-        let mut request = self.http_client.get(&url);
-
         let mut query = vec![("network", params.network.to_string())];
 
         if let Some(agg) = params.aggregation {
@@ -124,19 +128,19 @@ impl PragmaClient {
             query.push(("routing", routing.to_string()));
         }
         if let Some(ts) = params.timestamp {
-            query.push(("timestamp", ts.to_string()));
+            query.push(("timestamp", ts.as_secs().to_string()));
         }
         if let Some(comps) = params.components {
             query.push(("components", comps.to_string()));
         }
 
-        request = request.query(&query);
-
-        let response = request
-            .send()
-            .await?
-            .json::<GetOnchainEntryResponse>()
+        let response: GetOnchainEntryResponse = self
+            .get_json(&format!("/node/v1/onchain/{base}/{quote}"), &query)
             .await?;
+
+        self.config
+            .clock
+            .check_freshness(response.last_updated_timestamp, params.max_age)?;
         Ok(response)
     }
 
@@ -147,7 +151,6 @@ impl PragmaClient {
         quote: &str,
         params: GetOnchainEntryParams,
     ) -> Result<GetOnchainEntryResponse, PragmaError> {
-        let runtime = Self::runtime();
-        runtime.block_on(self.get_onchain_entry(base, quote, params))
+        Self::block_on(self.get_onchain_entry(base, quote, params))
     }
 }
@@ -1,12 +1,49 @@
+// `PragmaError` holds the reqwest and tungstenite errors by value, which makes every `Result` of
+// the crate large. Boxing them would change the public error variants.
+#![allow(clippy::result_large_err)]
+
+mod aggregation;
+mod api;
+mod api_key;
+#[cfg(feature = "sync")]
+mod blocking;
+mod cassette;
 mod client;
+mod clock;
+mod components;
 mod config;
+mod deviation;
 mod errors;
+mod failover;
+mod health;
 mod http;
+#[cfg(feature = "mock-server")]
+pub mod mock_server;
+mod rate_limit;
+mod telemetry;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod timestamp;
 mod ws;
 
+pub use aggregation::{AggregationMethod, Aggregator, PriceSample, Sample};
+pub use api::PragmaApi;
+pub use api_key::{ApiKey, ApiKeyProvider, RotatingApiKey};
+#[cfg(feature = "sync")]
+pub use blocking::{BlockingPragmaClient, BlockingWsClient};
+pub use cassette::{read_cassette, CassetteEntry, ReplayTiming};
 pub use client::PragmaClient;
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use components::{ComponentDeviation, OutlierMethod};
 pub use config::{Config, Environment};
+pub use deviation::{Deviation, DeviationEvent, DeviationMonitor, DEFAULT_DEVIATION_INTERVAL};
 pub use errors::PragmaError;
+pub use failover::FailoverConfig;
+pub use health::{Health, HealthWatcher, DEFAULT_HEALTH_TIMEOUT};
+pub use rate_limit::{RateLimit, RateLimitConfig};
+#[cfg(feature = "metrics")]
+pub use telemetry::describe_metrics;
+pub use timestamp::Timestamp;
 
 // Re-export types from pragma_common
 pub use pragma_common::{
@@ -15,9 +52,14 @@ pub use pragma_common::{
 };
 
 // Offchain endpoints
-pub use http::offchain::entry::{Component, GetEntryParams, GetEntryResponse};
+pub use http::offchain::entry::{
+    Component, EntryCacheConfig, EntryCacheStats, GetEntryParams, GetEntryResponse,
+};
 pub use http::offchain::funding_rates::{
-    GetFundingRatesResponse, GetHistoricalFundingRatesResponse,
+    FundingRateInstrument, FundingRatesEntry, FundingRatesStats, FundingSource,
+    GetFundingRatesAllSourcesResponse, GetFundingRatesResponse, GetHistoricalFundingRatesResponse,
+    HistoryChunking, ListFundingRateInstrumentsResponse, DEFAULT_HISTORY_CHUNK,
+    DEFAULT_HISTORY_CONCURRENCY, DEFAULT_SOURCES_CONCURRENCY,
 };
 
 // Onchain endpoints
@@ -27,6 +69,11 @@ pub use http::onchain::get_onchain_entry::{
 
 // Websocket endpoints
 pub use ws::{
+    candles::{Candle, CandleAggregator, LateTickPolicy},
+    hub::{HubSubscription, SubscriptionHub, DEFAULT_HUB_CAPACITY},
     lightspeed::{LightspeedMessage, PriceUpdate},
+    price_book::{BookPrice, PriceBook, PriceOrigin, DEFAULT_PRICE_BOOK_MAX_AGE},
+    rolling::{RollingConfig, RollingStats, RollingStream, RollingWindows},
     starkex::{PriceUpdate as StarkexPriceUpdate, SignedPrice, StarkexMessage},
+    PairMessage, PragmaWsClient, ReconnectConfig, Timestamped, WsError,
 };
@@ -0,0 +1,294 @@
+mod routes;
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use tokio::sync::{broadcast, oneshot};
+
+use crate::{
+    Config, Environment, FundingRateInstrument, FundingRatesEntry, GetEntryResponse,
+    GetOnchainEntryResponse, LightspeedMessage, StarkexMessage,
+};
+
+/// A fault injected in the responses of a [`MockServer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fault {
+    /// Delays every HTTP response and WebSocket upgrade.
+    Latency(Duration),
+    /// Answers every HTTP request with this status code.
+    Status(u16),
+    /// Answers every HTTP request with a body that is not valid JSON.
+    MalformedJson,
+    /// Answers every HTTP request with a `429` whose `Retry-After` header is this delay.
+    RateLimited(Duration),
+}
+
+/// An HTTP request received by a [`MockServer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub query: Option<String>,
+    pub api_key: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum WsEvent {
+    Frame(String),
+    Drop,
+}
+
+#[derive(Default)]
+pub(crate) struct Fixtures {
+    pub(crate) entries: HashMap<String, GetEntryResponse>,
+    pub(crate) onchain_entries: HashMap<String, GetOnchainEntryResponse>,
+    pub(crate) funding_rates: HashMap<(String, String), FundingRatesEntry>,
+    pub(crate) historical_funding_rates: HashMap<(String, String), Vec<FundingRatesEntry>>,
+    pub(crate) instruments: Vec<FundingRateInstrument>,
+}
+
+pub(crate) struct ServerState {
+    pub(crate) fixtures: Mutex<Fixtures>,
+    pub(crate) faults: Mutex<Vec<Fault>>,
+    pub(crate) api_key: Mutex<Option<String>>,
+    pub(crate) version: Mutex<Option<String>>,
+    pub(crate) requests: Mutex<Vec<RecordedRequest>>,
+    pub(crate) ws_messages: Mutex<Vec<String>>,
+    pub(crate) lightspeed: broadcast::Sender<WsEvent>,
+    pub(crate) starkex: broadcast::Sender<WsEvent>,
+}
+
+/// Locks `mutex`, ignoring poisoning: the fixtures stay usable after a panicking test.
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Key of the pair fixtures, case insensitive.
+pub(crate) fn pair_key(base: &str, quote: &str) -> String {
+    format!("{base}/{quote}").to_uppercase()
+}
+
+/// In-process HTTP and WebSocket server emulating the Pragma API, for offline tests.
+///
+/// It serves `/node`, the offchain entry, onchain entry and funding rates routes from scriptable
+/// fixtures, and both WebSocket subscribe endpoints from messages pushed by the test. Routes without
+/// a fixture answer with a 404. The server stops when dropped.
+///
+/// # Examples
+///
+/// ```
+/// use pragma_rs::{mock_server::MockServer, GetEntryResponse, PragmaClient, Timestamp};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let server = MockServer::start().await.unwrap();
+///     server.set_entry(
+///         "BTC",
+///         "USD",
+///         GetEntryResponse {
+///             decimals: 8,
+///             num_sources_aggregated: 5,
+///             pair_id: "BTC/USD".to_string(),
+///             price: "0x5f5e100".to_string(),
+///             timestamp: Timestamp::from_millis(1_746_448_809_000),
+///             components: None,
+///         },
+///     );
+///
+///     let client = PragmaClient::new(server.config("test_api_key")).unwrap();
+///     let entry = client.get_entry("BTC", "USD", None).await.unwrap();
+///     assert_eq!(entry.price_u128().unwrap(), 100_000_000);
+/// }
+/// ```
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<ServerState>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl std::fmt::Debug for MockServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MockServer")
+            .field("addr", &self.addr)
+            .finish_non_exhaustive()
+    }
+}
+
+impl MockServer {
+    /// Starts a server listening on a random local port.
+    pub async fn start() -> std::io::Result<Self> {
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", 0)).await?;
+        let addr = listener.local_addr()?;
+
+        let (lightspeed, _) = broadcast::channel(1024);
+        let (starkex, _) = broadcast::channel(1024);
+        let state = Arc::new(ServerState {
+            fixtures: Mutex::new(Fixtures::default()),
+            faults: Mutex::new(Vec::new()),
+            api_key: Mutex::new(None),
+            version: Mutex::new(None),
+            requests: Mutex::new(Vec::new()),
+            ws_messages: Mutex::new(Vec::new()),
+            lightspeed,
+            starkex,
+        });
+
+        let (shutdown, shutdown_receiver) = oneshot::channel::<()>();
+        let router = routes::router(state.clone());
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, router)
+                .with_graceful_shutdown(async {
+                    let _ = shutdown_receiver.await;
+                })
+                .await;
+        });
+
+        Ok(Self {
+            addr,
+            state,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    /// The base URL of the HTTP endpoints, e.g. `http://127.0.0.1:4242`.
+    pub fn http_base_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// The base URL of the WebSocket endpoints, e.g. `ws://127.0.0.1:4242`.
+    pub fn ws_base_url(&self) -> String {
+        format!("ws://{}", self.addr)
+    }
+
+    /// An `Environment::Local` pointing to this server.
+    pub fn environment(&self) -> Environment {
+        Environment::Local {
+            http_base_url: self.http_base_url(),
+            ws_base_url: self.ws_base_url(),
+        }
+    }
+
+    /// A `Config` pointing to this server.
+    pub fn config(&self, api_key: &str) -> Config {
+        Config::new(api_key.to_string(), self.environment())
+    }
+
+    /// Rejects the requests that do not carry this API key with a 401.
+    pub fn require_api_key(&self, api_key: &str) {
+        *lock(&self.state.api_key) = Some(api_key.to_string());
+    }
+
+    /// Sends `version` in the `x-pragma-version` header of the `/node` responses.
+    pub fn set_version(&self, version: &str) {
+        *lock(&self.state.version) = Some(version.to_string());
+    }
+
+    /// Serves `response` on `/node/v1/data/{base}/{quote}`.
+    pub fn set_entry(&self, base: &str, quote: &str, response: GetEntryResponse) {
+        lock(&self.state.fixtures)
+            .entries
+            .insert(pair_key(base, quote), response);
+    }
+
+    /// Serves `response` on `/node/v1/onchain/{base}/{quote}`, whatever the network.
+    pub fn set_onchain_entry(&self, base: &str, quote: &str, response: GetOnchainEntryResponse) {
+        lock(&self.state.fixtures)
+            .onchain_entries
+            .insert(pair_key(base, quote), response);
+    }
+
+    /// Serves `entry` on `/node/v1/funding_rates/{base}/{quote}` for the source of the entry.
+    pub fn set_funding_rates(&self, base: &str, quote: &str, entry: FundingRatesEntry) {
+        let key = (pair_key(base, quote), entry.source.to_lowercase());
+        lock(&self.state.fixtures).funding_rates.insert(key, entry);
+    }
+
+    /// Serves `entries` on `/node/v1/funding_rates/history/{base}/{quote}` for `source`, filtered by the
+    /// requested time range.
+    pub fn set_historical_funding_rates(
+        &self,
+        base: &str,
+        quote: &str,
+        source: &str,
+        entries: Vec<FundingRatesEntry>,
+    ) {
+        let key = (pair_key(base, quote), source.to_lowercase());
+        lock(&self.state.fixtures)
+            .historical_funding_rates
+            .insert(key, entries);
+    }
+
+    /// Serves `instruments` on `/node/v1/funding_rates/instruments`.
+    pub fn set_funding_rate_instruments(&self, instruments: Vec<FundingRateInstrument>) {
+        lock(&self.state.fixtures).instruments = instruments;
+    }
+
+    /// Injects `fault` in every subsequent response, until [`MockServer::clear_faults`] is called.
+    pub fn inject_fault(&self, fault: Fault) {
+        lock(&self.state.faults).push(fault);
+    }
+
+    /// Removes every injected fault.
+    pub fn clear_faults(&self) {
+        lock(&self.state.faults).clear();
+    }
+
+    /// Sends `message` to every client connected to the Lightspeed endpoint.
+    pub fn push_lightspeed(&self, message: &LightspeedMessage) {
+        if let Ok(frame) = serde_json::to_string(message) {
+            self.push_lightspeed_raw(frame);
+        }
+    }
+
+    /// Sends a raw text frame, valid or not, to every client connected to the Lightspeed endpoint.
+    pub fn push_lightspeed_raw(&self, frame: String) {
+        let _ = self.state.lightspeed.send(WsEvent::Frame(frame));
+    }
+
+    /// Sends `message` to every client connected to the StarkEx endpoint.
+    pub fn push_starkex(&self, message: &StarkexMessage) {
+        // The StarkEx endpoint sends its messages as JSON encoded strings.
+        if let Ok(frame) = serde_json::to_string(message).and_then(|m| serde_json::to_string(&m)) {
+            self.push_starkex_raw(frame);
+        }
+    }
+
+    /// Sends a raw text frame, valid or not, to every client connected to the StarkEx endpoint.
+    pub fn push_starkex_raw(&self, frame: String) {
+        let _ = self.state.starkex.send(WsEvent::Frame(frame));
+    }
+
+    /// Closes every open WebSocket connection without a close handshake.
+    pub fn drop_ws_connections(&self) {
+        let _ = self.state.lightspeed.send(WsEvent::Drop);
+        let _ = self.state.starkex.send(WsEvent::Drop);
+    }
+
+    /// The number of clients currently connected to the Lightspeed and StarkEx endpoints.
+    pub fn ws_connection_count(&self) -> usize {
+        self.state.lightspeed.receiver_count() + self.state.starkex.receiver_count()
+    }
+
+    /// Every HTTP request received so far, in order.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        lock(&self.state.requests).clone()
+    }
+
+    /// Every text frame received from WebSocket clients so far, in order.
+    pub fn ws_messages(&self) -> Vec<String> {
+        lock(&self.state.ws_messages).clone()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.drop_ws_connections();
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, Request, State,
+    },
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use futures_util::{SinkExt, StreamExt};
+use tokio::sync::broadcast;
+
+use crate::health::VERSION_HEADER;
+
+use super::{lock, pair_key, Fault, RecordedRequest, ServerState, WsEvent};
+
+type Params = Query<HashMap<String, String>>;
+
+pub(crate) fn router(state: Arc<ServerState>) -> Router {
+    Router::new()
+        .route("/node", get(node))
+        .route("/node/v1/data/{base}/{quote}", get(get_entry))
+        .route("/node/v1/onchain/{base}/{quote}", get(get_onchain_entry))
+        .route(
+            "/node/v1/funding_rates/instruments",
+            get(get_funding_rate_instruments),
+        )
+        .route(
+            "/node/v1/funding_rates/{base}/{quote}",
+            get(get_funding_rates),
+        )
+        .route(
+            "/node/v1/funding_rates/history/{base}/{quote}",
+            get(get_historical_funding_rates),
+        )
+        .route("/node/v1/data/price/subscribe", get(lightspeed_ws))
+        .route("/node/v1/data/subscribe", get(starkex_ws))
+        .layer(middleware::from_fn_with_state(state.clone(), intercept))
+        .with_state(state)
+}
+
+async fn node(State(state): State<Arc<ServerState>>) -> Response {
+    match lock(&state.version).clone() {
+        Some(version) => ([(VERSION_HEADER, version)], "Server is running!").into_response(),
+        None => "Server is running!".into_response(),
+    }
+}
+
+/// Records the request, checks the API key and applies the injected faults.
+async fn intercept(
+    State(state): State<Arc<ServerState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let api_key = request
+        .headers()
+        .get("x-api-key")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    lock(&state.requests).push(RecordedRequest {
+        method: request.method().to_string(),
+        path: request.uri().path().to_string(),
+        query: request.uri().query().map(str::to_string),
+        api_key: api_key.clone(),
+    });
+
+    let faults = lock(&state.faults).clone();
+    for fault in &faults {
+        if let Fault::Latency(latency) = fault {
+            tokio::time::sleep(*latency).await;
+        }
+    }
+
+    let expected_api_key = lock(&state.api_key).clone();
+    if expected_api_key.is_some() && expected_api_key != api_key {
+        return (StatusCode::UNAUTHORIZED, "Invalid API key").into_response();
+    }
+
+    for fault in &faults {
+        match fault {
+            Fault::Status(code) => {
+                let status =
+                    StatusCode::from_u16(*code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+                return (status, "Injected fault").into_response();
+            }
+            Fault::MalformedJson => {
+                return ([(header::CONTENT_TYPE, "application/json")], "{\"price\": ")
+                    .into_response();
+            }
+            Fault::RateLimited(retry_after) => {
+                let retry_after = retry_after.as_secs_f64().to_string();
+                return (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    [(header::RETRY_AFTER, retry_after)],
+                    "Injected fault",
+                )
+                    .into_response();
+            }
+            Fault::Latency(_) => {}
+        }
+    }
+
+    next.run(request).await
+}
+
+fn not_found(what: String) -> Response {
+    (StatusCode::NOT_FOUND, format!("No fixture for {what}")).into_response()
+}
+
+async fn get_entry(
+    State(state): State<Arc<ServerState>>,
+    Path((base, quote)): Path<(String, String)>,
+) -> Response {
+    let key = pair_key(&base, &quote);
+    match lock(&state.fixtures).entries.get(&key) {
+        Some(entry) => Json(entry).into_response(),
+        None => not_found(key),
+    }
+}
+
+async fn get_onchain_entry(
+    State(state): State<Arc<ServerState>>,
+    Path((base, quote)): Path<(String, String)>,
+) -> Response {
+    let key = pair_key(&base, &quote);
+    match lock(&state.fixtures).onchain_entries.get(&key) {
+        Some(entry) => Json(entry).into_response(),
+        None => not_found(key),
+    }
+}
+
+async fn get_funding_rate_instruments(
+    State(state): State<Arc<ServerState>>,
+    Query(params): Params,
+) -> Response {
+    let fixtures = lock(&state.fixtures);
+    let instruments: Vec<_> = fixtures
+        .instruments
+        .iter()
+        .filter(|i| {
+            params.get("source").map_or(true, |source| {
+                i.source.as_str().eq_ignore_ascii_case(source)
+            })
+        })
+        .collect();
+    Json(instruments).into_response()
+}
+
+async fn get_funding_rates(
+    State(state): State<Arc<ServerState>>,
+    Path((base, quote)): Path<(String, String)>,
+    Query(params): Params,
+) -> Response {
+    let source = params.get("source").cloned().unwrap_or_default();
+    let key = (pair_key(&base, &quote), source.to_lowercase());
+    match lock(&state.fixtures).funding_rates.get(&key) {
+        Some(entry) => Json(entry).into_response(),
+        None => not_found(format!("{} on {}", key.0, key.1)),
+    }
+}
+
+async fn get_historical_funding_rates(
+    State(state): State<Arc<ServerState>>,
+    Path((base, quote)): Path<(String, String)>,
+    Query(params): Params,
+) -> Response {
+    let source = params.get("source").cloned().unwrap_or_default();
+    let range = params.get("timestamp").and_then(|ts| {
+        let (from, to) = ts.split_once(',')?;
+        Some((from.parse::<u64>().ok()?, to.parse::<u64>().ok()?))
+    });
+    let Some((from, to)) = range else {
+        return (StatusCode::BAD_REQUEST, "Invalid timestamp range").into_response();
+    };
+
+    let key = (pair_key(&base, &quote), source.to_lowercase());
+    match lock(&state.fixtures).historical_funding_rates.get(&key) {
+        Some(entries) => {
+            let entries: Vec<_> = entries
+                .iter()
+                .filter(|e| (from..=to).contains(&e.timestamp.as_millis()))
+                .collect();
+            Json(entries).into_response()
+        }
+        None => not_found(format!("{} on {}", key.0, key.1)),
+    }
+}
+
+async fn lightspeed_ws(State(state): State<Arc<ServerState>>, ws: WebSocketUpgrade) -> Response {
+    let events = state.lightspeed.subscribe();
+    ws.on_upgrade(move |socket| serve_ws(socket, state, events, false))
+}
+
+async fn starkex_ws(State(state): State<Arc<ServerState>>, ws: WebSocketUpgrade) -> Response {
+    let events = state.starkex.subscribe();
+    ws.on_upgrade(move |socket| serve_ws(socket, state, events, true))
+}
+
+/// Forwards the pushed frames to the client, and acknowledges its (un)subscriptions by echoing them.
+async fn serve_ws(
+    socket: WebSocket,
+    state: Arc<ServerState>,
+    mut events: broadcast::Receiver<WsEvent>,
+    string_encoded: bool,
+) {
+    let (mut write, mut read) = socket.split();
+    loop {
+        tokio::select! {
+            message = read.next() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text.to_string(),
+                    Some(Ok(_)) => continue,
+                    Some(Err(_)) | None => break,
+                };
+                lock(&state.ws_messages).push(text.clone());
+                let ack = if string_encoded {
+                    serde_json::to_string(&text).unwrap_or_default()
+                } else {
+                    text
+                };
+                if write.send(Message::Text(ack.into())).await.is_err() {
+                    break;
+                }
+            }
+            event = events.recv() => {
+                match event {
+                    Ok(WsEvent::Frame(frame)) => {
+                        if write.send(Message::Text(frame.into())).await.is_err() {
+                            break;
+                        }
+                    }
+                    Ok(WsEvent::Drop) | Err(broadcast::error::RecvError::Closed) => break,
+                    Err(broadcast::error::RecvError::Lagged(_)) => {}
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use std::{
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use reqwest::header::HeaderMap;
+
+use crate::{PragmaError, Timestamp};
+
+/// The longest a request sleeps before checking its bucket again.
+const MAX_WAIT: Duration = Duration::from_secs(60);
+
+/// A token bucket quota: `burst` requests at once, refilled at `requests_per_second`, which must be
+/// positive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub requests_per_second: f64,
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// A quota of `requests_per_second`, with bursts of the same size.
+    pub fn per_second(requests_per_second: u32) -> Self {
+        Self {
+            requests_per_second: f64::from(requests_per_second),
+            burst: requests_per_second.max(1),
+        }
+    }
+}
+
+/// Client-side rate limiting of the HTTP endpoints, see [`crate::Config::with_rate_limit`].
+///
+/// Every request waits for a token of the `global` bucket. Requests whose path starts with one of
+/// the `endpoints` prefixes (e.g. `/node/v1/funding_rates/history`) also wait for a token of the
+/// bucket of the longest matching prefix.
+///
+/// The limiter also adapts to the API: after a `429` carrying a `Retry-After` header, or a response
+/// with `x-ratelimit-remaining: 0` and `x-ratelimit-reset`, requests are held until the quota resets.
+/// A request rejected with a `429` carrying a `Retry-After` header is retried once, after the delay;
+/// it fails with `PragmaError::RateLimited` if it is rejected again.
+///
+/// Every `requests_per_second` must be positive, or `PragmaClient::new` fails with
+/// `PragmaError::InvalidConfig`.
+///
+/// # Examples
+///
+/// ```
+/// use pragma_rs::{Config, Environment, RateLimit, RateLimitConfig};
+///
+/// let rate_limit = RateLimitConfig::new(RateLimit::per_second(10))
+///     .endpoint("/node/v1/funding_rates/history", RateLimit::per_second(2));
+/// let config = Config::new("your_api_key".to_string(), Environment::Development)
+///     .with_rate_limit(rate_limit);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitConfig {
+    pub global: RateLimit,
+    pub endpoints: Vec<(String, RateLimit)>,
+}
+
+impl RateLimitConfig {
+    pub fn new(global: RateLimit) -> Self {
+        Self {
+            global,
+            endpoints: Vec::new(),
+        }
+    }
+
+    /// Adds a quota for the requests whose path starts with `path_prefix`.
+    pub fn endpoint(mut self, path_prefix: impl Into<String>, limit: RateLimit) -> Self {
+        self.endpoints.push((path_prefix.into(), limit));
+        self
+    }
+    /// Checks that every quota refills at a positive rate, so that no request waits forever.
+    pub(crate) fn validate(&self) -> Result<(), PragmaError> {
+        let quotas = std::iter::once(("global", &self.global)).chain(
+            self.endpoints
+                .iter()
+                .map(|(prefix, limit)| (prefix.as_str(), limit)),
+        );
+        for (name, limit) in quotas {
+            if !(limit.requests_per_second > 0.0 && limit.requests_per_second.is_finite()) {
+                return Err(PragmaError::InvalidConfig(format!(
+                    "the {name} rate limit must allow a positive finite number of requests per \
+                     second, got {}",
+                    limit.requests_per_second
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    refilled_at: Instant,
+    blocked_until: Option<Instant>,
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    limit: RateLimit,
+    state: Mutex<BucketState>,
+}
+
+impl TokenBucket {
+    fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            state: Mutex::new(BucketState {
+                tokens: f64::from(limit.burst),
+                refilled_at: Instant::now(),
+                blocked_until: None,
+            }),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, BucketState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Takes a token, or returns how long to wait before trying again.
+    fn try_acquire(&self) -> Result<(), Duration> {
+        let mut state = self.state();
+        let now = Instant::now();
+
+        if let Some(blocked_until) = state.blocked_until {
+            if blocked_until > now {
+                return Err((blocked_until - now).min(MAX_WAIT));
+            }
+            state.blocked_until = None;
+        }
+
+        let elapsed = now.duration_since(state.refilled_at).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * self.limit.requests_per_second)
+            .min(f64::from(self.limit.burst.max(1)));
+        state.refilled_at = now;
+
+        if state.tokens >= 1.0 {
+            state.tokens -= 1.0;
+            Ok(())
+        } else if self.limit.requests_per_second > 0.0 {
+            let missing = 1.0 - state.tokens;
+            // A tiny rate makes the wait overflow a `Duration`.
+            let wait = Duration::try_from_secs_f64(missing / self.limit.requests_per_second);
+            Err(wait.map_or(MAX_WAIT, |wait| wait.min(MAX_WAIT)))
+        } else {
+            Err(Duration::from_secs(1))
+        }
+    }
+
+    async fn acquire(&self) {
+        while let Err(wait) = self.try_acquire() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    fn block_for(&self, duration: Duration) {
+        let mut state = self.state();
+        let now = Instant::now();
+        let until = now.checked_add(duration).unwrap_or(now + MAX_WAIT);
+        state.blocked_until = Some(state.blocked_until.map_or(until, |b| b.max(until)));
+        state.tokens = 0.0;
+    }
+
+    fn cap_tokens(&self, remaining: f64) {
+        let mut state = self.state();
+        state.tokens = state.tokens.min(remaining);
+    }
+}
+
+/// The buckets of a `RateLimitConfig`, shared by the clones of a `PragmaClient`.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    global: TokenBucket,
+    endpoints: Vec<(String, TokenBucket)>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(config: &RateLimitConfig) -> Self {
+        Self {
+            global: TokenBucket::new(config.global),
+            endpoints: config
+                .endpoints
+                .iter()
+                .map(|(prefix, limit)| (prefix.clone(), TokenBucket::new(*limit)))
+                .collect(),
+        }
+    }
+
+    fn endpoint(&self, path: &str) -> Option<&TokenBucket> {
+        self.endpoints
+            .iter()
+            .filter(|(prefix, _)| path.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, bucket)| bucket)
+    }
+
+    /// Waits until a request to `path` is allowed.
+    pub(crate) async fn acquire(&self, path: &str) {
+        if let Some(bucket) = self.endpoint(path) {
+            bucket.acquire().await;
+        }
+        self.global.acquire().await;
+    }
+
+    /// Adjusts the global bucket to the rate limit headers of a response.
+    pub(crate) fn observe(&self, headers: &HeaderMap) {
+        if let Some(retry_after) = retry_after(headers) {
+            self.global.block_for(retry_after);
+            return;
+        }
+
+        let Some(remaining) = header_f64(headers, "x-ratelimit-remaining") else {
+            return;
+        };
+        self.global.cap_tokens(remaining);
+        if remaining < 1.0 {
+            if let Some(reset) = header_f64(headers, "x-ratelimit-reset").and_then(reset_delay) {
+                self.global.block_for(reset);
+            }
+        }
+    }
+}
+
+fn header_f64(headers: &HeaderMap, name: &str) -> Option<f64> {
+    headers
+        .get(name)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite())
+}
+
+/// Parses a `Retry-After` header given in seconds.
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    header_f64(headers, "retry-after").and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+}
+
+/// Converts `x-ratelimit-reset` to a delay. It is either a number of seconds, or a Unix timestamp
+/// in seconds for the APIs following GitHub's convention.
+fn reset_delay(reset: f64) -> Option<Duration> {
+    const UNIX_TIMESTAMP_THRESHOLD: f64 = 1_000_000_000.0;
+
+    let secs = if reset >= UNIX_TIMESTAMP_THRESHOLD {
+        reset - Timestamp::now().as_millis() as f64 / 1000.0
+    } else {
+        reset
+    };
+    Duration::try_from_secs_f64(secs.max(0.0)).ok()
+}
@@ -0,0 +1,212 @@
+//! Instrumentation of the HTTP and WebSocket clients.
+//!
+//! The macros expand to nothing unless the `tracing` feature is enabled, and the functions do
+//! nothing unless the `metrics` feature is enabled, so call sites do not need their own `#[cfg]`.
+//! Nothing recorded here contains the API key: it is only ever sent as the `x-api-key` header,
+//! which is never traced.
+
+use std::time::Duration;
+
+use reqwest::StatusCode;
+
+use crate::Timestamp;
+
+/// Emits a `tracing` event at the given level, e.g. `trace_event!(debug, frame, "message sent")`.
+macro_rules! trace_event {
+    ($level:ident, $($arg:tt)+) => {{
+        #[cfg(feature = "tracing")]
+        tracing::$level!($($arg)+);
+    }};
+}
+
+/// Records fields of the current span, e.g. `trace_record!("status" = status.as_u16())`.
+macro_rules! trace_record {
+    ($($field:literal = $value:expr),+ $(,)?) => {{
+        #[cfg(feature = "tracing")]
+        {
+            let span = tracing::Span::current();
+            $(span.record($field, $value);)+
+        }
+    }};
+}
+
+pub(crate) use trace_event;
+pub(crate) use trace_record;
+
+/// Returns the name of the endpoint serving `path`, and the trading pair it targets if any.
+#[cfg(any(feature = "tracing", feature = "metrics"))]
+pub(crate) fn endpoint(path: &str) -> (&'static str, Option<&str>) {
+    const ENDPOINTS: [(&str, &str); 4] = [
+        ("/node/v1/data/", "get_entry"),
+        ("/node/v1/onchain/", "get_onchain_entry"),
+        (
+            "/node/v1/funding_rates/history/",
+            "get_historical_funding_rates",
+        ),
+        ("/node/v1/funding_rates/", "get_funding_rates"),
+    ];
+
+    match path {
+        "/node" => return ("is_healthy", None),
+        "/node/v1/funding_rates/instruments" => return ("list_funding_rate_instruments", None),
+        _ => {}
+    }
+    ENDPOINTS
+        .iter()
+        .find_map(|(prefix, name)| {
+            let pair = path.strip_prefix(prefix)?;
+            (pair.matches('/').count() == 1).then_some((*name, Some(pair)))
+        })
+        .unwrap_or(("other", None))
+}
+
+/// Returns the name of the WebSocket stream served at `path`.
+pub(crate) fn ws_stream(path: &str) -> &'static str {
+    match path {
+        "/node/v1/data/price/subscribe" => "lightspeed",
+        "/node/v1/data/subscribe" => "starkex",
+        _ => "other",
+    }
+}
+
+/// Registers the descriptions of the metrics emitted by the crate with the installed recorder.
+///
+/// Metrics are emitted through the [`metrics`] facade, so they are exported by whatever recorder
+/// the application installs (e.g. `metrics-exporter-prometheus`). Calling this function is optional,
+/// it only adds help texts and units:
+///
+/// * `pragma_http_requests_total` (counter, labels `endpoint`, `status`): HTTP requests sent,
+///   `status` is `error` when no response was received.
+/// * `pragma_http_request_duration_seconds` (histogram, labels `endpoint`, `status`).
+/// * `pragma_entry_cache_requests_total` (counter, label `result`: `hit`, `stale_hit` or `miss`).
+/// * `pragma_ws_connections_total` and `pragma_ws_disconnections_total` (counters, label `stream`).
+/// * `pragma_ws_reconnects_total` (counter, label `stream`): connections reopened by a client after
+///   losing its connection or failing back, also counted in `pragma_ws_connections_total`.
+/// * `pragma_ws_messages_received_total` (counter, label `stream`).
+/// * `pragma_ws_parse_failures_total` (counter, label `stream`).
+/// * `pragma_ws_queue_depth` (gauge, label `stream`): messages received but not consumed yet.
+/// * `pragma_ws_last_update_timestamp_seconds` (gauge, labels `stream`, `pair`): timestamp of the
+///   last price received for a pair, the time since the last update being
+///   `time() - pragma_ws_last_update_timestamp_seconds` in PromQL.
+#[cfg(feature = "metrics")]
+pub fn describe_metrics() {
+    use metrics::{describe_counter, describe_gauge, describe_histogram, Unit};
+
+    describe_counter!(
+        "pragma_http_requests_total",
+        "HTTP requests sent to the Pragma API."
+    );
+    describe_histogram!(
+        "pragma_http_request_duration_seconds",
+        Unit::Seconds,
+        "Latency of the HTTP requests sent to the Pragma API."
+    );
+    describe_counter!(
+        "pragma_entry_cache_requests_total",
+        "Requests served by the get_entry cache."
+    );
+    describe_counter!(
+        "pragma_ws_connections_total",
+        "WebSocket connections established."
+    );
+    describe_counter!(
+        "pragma_ws_disconnections_total",
+        "WebSocket connections closed or lost."
+    );
+    describe_counter!(
+        "pragma_ws_reconnects_total",
+        "WebSocket connections reopened by a client."
+    );
+    describe_counter!(
+        "pragma_ws_messages_received_total",
+        "WebSocket messages received."
+    );
+    describe_counter!(
+        "pragma_ws_parse_failures_total",
+        "WebSocket messages that could not be parsed."
+    );
+    describe_gauge!(
+        "pragma_ws_queue_depth",
+        "WebSocket messages received but not consumed yet."
+    );
+    describe_gauge!(
+        "pragma_ws_last_update_timestamp_seconds",
+        Unit::Seconds,
+        "Timestamp of the last price received for a pair."
+    );
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn http_request(path: &str, status: Option<StatusCode>, latency: Duration) {
+    #[cfg(feature = "metrics")]
+    {
+        let endpoint = endpoint(path).0;
+        let status = status.map_or_else(|| "error".to_string(), |s| s.as_u16().to_string());
+        metrics::counter!(
+            "pragma_http_requests_total",
+            "endpoint" => endpoint,
+            "status" => status.clone()
+        )
+        .increment(1);
+        metrics::histogram!(
+            "pragma_http_request_duration_seconds",
+            "endpoint" => endpoint,
+            "status" => status
+        )
+        .record(latency.as_secs_f64());
+    }
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn entry_cache(result: &'static str) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("pragma_entry_cache_requests_total", "result" => result).increment(1);
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn ws_connected(stream: &'static str) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("pragma_ws_connections_total", "stream" => stream).increment(1);
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn ws_disconnected(stream: &'static str) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("pragma_ws_disconnections_total", "stream" => stream).increment(1);
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn ws_reconnected(stream: &'static str) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("pragma_ws_reconnects_total", "stream" => stream).increment(1);
+}
+
+/// Counts a received message, `parsed` telling whether the message handler accepted it.
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn ws_message(stream: &'static str, parsed: bool) {
+    #[cfg(feature = "metrics")]
+    {
+        metrics::counter!("pragma_ws_messages_received_total", "stream" => stream).increment(1);
+        if !parsed {
+            metrics::counter!("pragma_ws_parse_failures_total", "stream" => stream).increment(1);
+        }
+    }
+}
+
+/// Tracks the messages waiting in the incoming channel of a client.
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn ws_queue(stream: &'static str, delta: f64) {
+    #[cfg(feature = "metrics")]
+    metrics::gauge!("pragma_ws_queue_depth", "stream" => stream).increment(delta);
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn ws_price_update(stream: &'static str, pair: &str, timestamp: Timestamp) {
+    #[cfg(feature = "metrics")]
+    metrics::gauge!(
+        "pragma_ws_last_update_timestamp_seconds",
+        "stream" => stream,
+        "pair" => pair.to_string()
+    )
+    .set(timestamp.as_millis() as f64 / 1000.0);
+}
@@ -0,0 +1,264 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use async_trait::async_trait;
+
+use crate::{
+    FundingSource, GetEntryParams, GetEntryResponse, GetFundingRatesResponse,
+    GetHistoricalFundingRatesResponse, GetOnchainEntryParams, GetOnchainEntryResponse, PragmaApi,
+    PragmaError, Timestamp,
+};
+
+type Handler<R> = Arc<dyn Fn(MockCall) -> Result<R, PragmaError> + Send + Sync>;
+
+/// A call received by a [`MockPragmaClient`], with its arguments.
+#[derive(Debug, Clone)]
+pub enum MockCall {
+    GetEntry {
+        base: String,
+        quote: String,
+        params: Option<GetEntryParams>,
+    },
+    GetOnchainEntry {
+        base: String,
+        quote: String,
+        params: GetOnchainEntryParams,
+    },
+    GetFundingRates {
+        base: String,
+        quote: String,
+        source: FundingSource,
+        timestamp: Option<Timestamp>,
+    },
+    GetHistoricalFundingRates {
+        base: String,
+        quote: String,
+        from_ts: Timestamp,
+        to_ts: Timestamp,
+        source: FundingSource,
+    },
+    IsHealthy,
+}
+
+#[derive(Default)]
+struct MockState {
+    calls: Vec<MockCall>,
+    healthy: bool,
+    get_entry: Option<Handler<GetEntryResponse>>,
+    get_onchain_entry: Option<Handler<GetOnchainEntryResponse>>,
+    get_funding_rates: Option<Handler<GetFundingRatesResponse>>,
+    get_historical_funding_rates: Option<Handler<GetHistoricalFundingRatesResponse>>,
+}
+
+/// In-memory implementation of [`PragmaApi`] with programmable responses.
+///
+/// Every call is recorded and can be inspected with [`MockPragmaClient::calls`]. Endpoints without a
+/// programmed response return a `PragmaError::ApiError`. Clones share the same state.
+///
+/// # Examples
+///
+/// ```
+/// use pragma_rs::{testing::{MockCall, MockPragmaClient}, GetEntryResponse, PragmaApi, Timestamp};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let mock = MockPragmaClient::new();
+///     mock.on_get_entry(|call| {
+///         let MockCall::GetEntry { base, quote, .. } = call else { unreachable!() };
+///         Ok(GetEntryResponse {
+///             decimals: 8,
+///             num_sources_aggregated: 5,
+///             pair_id: format!("{base}/{quote}"),
+///             price: "0x5f5e100".to_string(),
+///             timestamp: Timestamp::from_millis(1_746_448_809_000),
+///             components: None,
+///         })
+///     });
+///
+///     let entry = mock.get_entry("BTC", "USD", None).await.unwrap();
+///     assert_eq!(entry.pair_id, "BTC/USD");
+///     assert_eq!(mock.calls().len(), 1);
+///     assert!(MockPragmaClient::default().is_healthy().await);
+/// }
+/// ```
+#[derive(Clone)]
+pub struct MockPragmaClient {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl std::fmt::Debug for MockPragmaClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MockPragmaClient")
+            .field("calls", &self.state().calls)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Same as [`MockPragmaClient::new`]: a healthy mock without any programmed response.
+impl Default for MockPragmaClient {
+    fn default() -> Self {
+        let state = MockState {
+            healthy: true,
+            ..MockState::default()
+        };
+        Self {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+}
+
+impl MockPragmaClient {
+    /// Creates a healthy mock without any programmed response.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Sets the value returned by `is_healthy`.
+    pub fn set_healthy(&self, healthy: bool) {
+        self.state().healthy = healthy;
+    }
+
+    /// Programs the response of `get_entry`. The handler receives the recorded `MockCall::GetEntry`.
+    pub fn on_get_entry<F>(&self, handler: F)
+    where
+        F: Fn(MockCall) -> Result<GetEntryResponse, PragmaError> + Send + Sync + 'static,
+    {
+        self.state().get_entry = Some(Arc::new(handler));
+    }
+
+    /// Programs the response of `get_onchain_entry`. The handler receives the recorded
+    /// `MockCall::GetOnchainEntry`.
+    pub fn on_get_onchain_entry<F>(&self, handler: F)
+    where
+        F: Fn(MockCall) -> Result<GetOnchainEntryResponse, PragmaError> + Send + Sync + 'static,
+    {
+        self.state().get_onchain_entry = Some(Arc::new(handler));
+    }
+
+    /// Programs the response of `get_funding_rates`. The handler receives the recorded
+    /// `MockCall::GetFundingRates`.
+    pub fn on_get_funding_rates<F>(&self, handler: F)
+    where
+        F: Fn(MockCall) -> Result<GetFundingRatesResponse, PragmaError> + Send + Sync + 'static,
+    {
+        self.state().get_funding_rates = Some(Arc::new(handler));
+    }
+
+    /// Programs the response of `get_historical_funding_rates`. The handler receives the recorded
+    /// `MockCall::GetHistoricalFundingRates`.
+    pub fn on_get_historical_funding_rates<F>(&self, handler: F)
+    where
+        F: Fn(MockCall) -> Result<GetHistoricalFundingRatesResponse, PragmaError>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.state().get_historical_funding_rates = Some(Arc::new(handler));
+    }
+
+    /// Returns every call received so far, in order.
+    pub fn calls(&self) -> Vec<MockCall> {
+        self.state().calls.clone()
+    }
+
+    /// Forgets the recorded calls, keeping the programmed responses.
+    pub fn clear_calls(&self) {
+        self.state().calls.clear();
+    }
+
+    fn respond<R>(
+        &self,
+        call: MockCall,
+        endpoint: &str,
+        handler: impl Fn(&MockState) -> Option<&Handler<R>>,
+    ) -> Result<R, PragmaError> {
+        // The handler is called without holding the lock, so it can use the mock itself.
+        let handler = {
+            let mut state = self.state();
+            state.calls.push(call.clone());
+            handler(&state).cloned()
+        };
+        match handler {
+            Some(handler) => handler(call),
+            None => Err(PragmaError::ApiError(format!(
+                "No mock response programmed for {endpoint}"
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl PragmaApi for MockPragmaClient {
+    async fn get_entry(
+        &self,
+        base: &str,
+        quote: &str,
+        params: Option<GetEntryParams>,
+    ) -> Result<GetEntryResponse, PragmaError> {
+        let call = MockCall::GetEntry {
+            base: base.to_string(),
+            quote: quote.to_string(),
+            params,
+        };
+        self.respond(call, "get_entry", |s| s.get_entry.as_ref())
+    }
+
+    async fn get_onchain_entry(
+        &self,
+        base: &str,
+        quote: &str,
+        params: GetOnchainEntryParams,
+    ) -> Result<GetOnchainEntryResponse, PragmaError> {
+        let call = MockCall::GetOnchainEntry {
+            base: base.to_string(),
+            quote: quote.to_string(),
+            params,
+        };
+        self.respond(call, "get_onchain_entry", |s| s.get_onchain_entry.as_ref())
+    }
+
+    async fn get_funding_rates(
+        &self,
+        base: &str,
+        quote: &str,
+        source: &FundingSource,
+        timestamp: Option<Timestamp>,
+    ) -> Result<GetFundingRatesResponse, PragmaError> {
+        let call = MockCall::GetFundingRates {
+            base: base.to_string(),
+            quote: quote.to_string(),
+            source: source.clone(),
+            timestamp,
+        };
+        self.respond(call, "get_funding_rates", |s| s.get_funding_rates.as_ref())
+    }
+
+    async fn get_historical_funding_rates(
+        &self,
+        base: &str,
+        quote: &str,
+        from_ts: Timestamp,
+        to_ts: Timestamp,
+        source: &FundingSource,
+    ) -> Result<GetHistoricalFundingRatesResponse, PragmaError> {
+        let call = MockCall::GetHistoricalFundingRates {
+            base: base.to_string(),
+            quote: quote.to_string(),
+            from_ts,
+            to_ts,
+            source: source.clone(),
+        };
+        self.respond(call, "get_historical_funding_rates", |s| {
+            s.get_historical_funding_rates.as_ref()
+        })
+    }
+
+    async fn is_healthy(&self) -> bool {
+        let mut state = self.state();
+        state.calls.push(MockCall::IsHealthy);
+        state.healthy
+    }
+}
@@ -0,0 +1,180 @@
+use std::{
+    fmt,
+    ops::{Add, Sub},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A point in time, stored as milliseconds since the Unix epoch.
+///
+/// The Pragma API mixes seconds and milliseconds depending on the endpoint. Every timestamp
+/// accepted or returned by the SDK is a `Timestamp`, and the conversion to the unit expected on
+/// the wire is done internally.
+///
+/// # Examples
+///
+/// ```
+/// use pragma_rs::Timestamp;
+///
+/// let ts = Timestamp::from_secs(1_746_448_809);
+/// assert_eq!(ts.as_millis(), 1_746_448_809_000);
+/// assert_eq!(ts, Timestamp::from_millis(1_746_448_809_000));
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(u64);
+
+impl Timestamp {
+    pub const UNIX_EPOCH: Self = Self(0);
+
+    /// Creates a `Timestamp` from milliseconds since the Unix epoch.
+    pub const fn from_millis(millis: u64) -> Self {
+        Self(millis)
+    }
+
+    /// Creates a `Timestamp` from seconds since the Unix epoch.
+    pub const fn from_secs(secs: u64) -> Self {
+        Self(secs.saturating_mul(1000))
+    }
+
+    /// Returns the current time.
+    pub fn now() -> Self {
+        Self::from(SystemTime::now())
+    }
+
+    /// Returns the number of milliseconds since the Unix epoch.
+    pub const fn as_millis(&self) -> u64 {
+        self.0
+    }
+
+    /// Returns the number of whole seconds since the Unix epoch.
+    pub const fn as_secs(&self) -> u64 {
+        self.0 / 1000
+    }
+
+    /// Returns the time elapsed from `earlier` to `self`, or zero if `earlier` is later.
+    pub const fn saturating_duration_since(&self, earlier: Self) -> Duration {
+        Duration::from_millis(self.0.saturating_sub(earlier.0))
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<SystemTime> for Timestamp {
+    fn from(time: SystemTime) -> Self {
+        let millis = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis());
+        Self(u64::try_from(millis).unwrap_or(u64::MAX))
+    }
+}
+
+impl From<Timestamp> for SystemTime {
+    fn from(ts: Timestamp) -> Self {
+        UNIX_EPOCH + Duration::from_millis(ts.0)
+    }
+}
+
+impl Add<Duration> for Timestamp {
+    type Output = Self;
+
+    fn add(self, rhs: Duration) -> Self {
+        let millis = u64::try_from(rhs.as_millis()).unwrap_or(u64::MAX);
+        Self(self.0.saturating_add(millis))
+    }
+}
+
+impl Sub<Duration> for Timestamp {
+    type Output = Self;
+
+    fn sub(self, rhs: Duration) -> Self {
+        let millis = u64::try_from(rhs.as_millis()).unwrap_or(u64::MAX);
+        Self(self.0.saturating_sub(millis))
+    }
+}
+
+/// (De)serializes as an integer number of milliseconds.
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        u64::deserialize(deserializer).map(Self::from_millis)
+    }
+}
+
+/// Serde adapter for timestamps encoded as an integer number of seconds.
+pub(crate) mod seconds {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::Timestamp;
+
+    pub fn serialize<S: Serializer>(ts: &Timestamp, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(ts.as_secs())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Timestamp, D::Error> {
+        u64::deserialize(deserializer).map(Timestamp::from_secs)
+    }
+}
+
+#[cfg(feature = "chrono")]
+mod chrono_impls {
+    use chrono::{DateTime, Utc};
+
+    use super::Timestamp;
+    use crate::PragmaError;
+
+    impl TryFrom<DateTime<Utc>> for Timestamp {
+        type Error = PragmaError;
+
+        fn try_from(time: DateTime<Utc>) -> Result<Self, Self::Error> {
+            u64::try_from(time.timestamp_millis())
+                .map(Self::from_millis)
+                .map_err(|_| PragmaError::TimestampOutOfRange(time.to_rfc3339()))
+        }
+    }
+
+    impl TryFrom<Timestamp> for DateTime<Utc> {
+        type Error = PragmaError;
+
+        fn try_from(ts: Timestamp) -> Result<Self, Self::Error> {
+            i64::try_from(ts.as_millis())
+                .ok()
+                .and_then(DateTime::from_timestamp_millis)
+                .ok_or_else(|| PragmaError::TimestampOutOfRange(ts.to_string()))
+        }
+    }
+}
+
+#[cfg(feature = "time")]
+mod time_impls {
+    use time::OffsetDateTime;
+
+    use super::Timestamp;
+    use crate::PragmaError;
+
+    impl TryFrom<OffsetDateTime> for Timestamp {
+        type Error = PragmaError;
+
+        fn try_from(time: OffsetDateTime) -> Result<Self, Self::Error> {
+            u64::try_from(time.unix_timestamp_nanos() / 1_000_000)
+                .map(Self::from_millis)
+                .map_err(|_| PragmaError::TimestampOutOfRange(time.to_string()))
+        }
+    }
+
+    impl TryFrom<Timestamp> for OffsetDateTime {
+        type Error = PragmaError;
+
+        fn try_from(ts: Timestamp) -> Result<Self, Self::Error> {
+            OffsetDateTime::from_unix_timestamp_nanos(i128::from(ts.as_millis()) * 1_000_000)
+                .map_err(|_| PragmaError::TimestampOutOfRange(ts.to_string()))
+        }
+    }
+}
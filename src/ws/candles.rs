@@ -0,0 +1,322 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Duration,
+};
+
+use pragma_common::interval::Interval;
+
+use crate::{LightspeedMessage, PragmaError, Timestamp};
+
+use super::lightspeed::PriceUpdate;
+
+/// What a `CandleAggregator` does with the ticks arriving after their candle was closed.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use pragma_rs::{CandleAggregator, Interval, LateTickPolicy, PriceUpdate, Timestamp};
+///
+/// let policy = LateTickPolicy::Grace(Duration::from_secs(2));
+/// let mut candles = CandleAggregator::new(Interval::OneSecond, policy);
+/// let tick = PriceUpdate {
+///     num_sources_aggregated: 5,
+///     pair_id: "ETH/USD".to_string(),
+///     price: "0x64".to_string(),
+/// };
+///
+/// candles.push_update(&tick, Timestamp::from_millis(10_500)).unwrap();
+/// candles.push_update(&tick, Timestamp::from_millis(11_500)).unwrap();
+/// // Delayed by a second: still applied to the candle of the 10th second.
+/// candles.push_update(&tick, Timestamp::from_millis(10_900)).unwrap();
+///
+/// // The candle of the 10th second closes 2 seconds after its end.
+/// assert!(candles.push_update(&tick, Timestamp::from_millis(12_500)).unwrap().is_empty());
+/// let closed = candles.push_update(&tick, Timestamp::from_millis(13_000)).unwrap();
+/// assert_eq!(closed[0].start, Timestamp::from_secs(10));
+/// assert_eq!(closed[0].ticks, 2);
+///
+/// candles.push_update(&tick, Timestamp::from_millis(10_950)).unwrap();
+/// assert_eq!(candles.late_ticks(), 1);
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LateTickPolicy {
+    /// Candles close as soon as a tick of a later interval is received. Out-of-order ticks are
+    /// still applied to the candle being built, older ones are dropped.
+    #[default]
+    Drop,
+
+    /// Candles stay open for this long after their end, measured with the tick timestamps, so
+    /// ticks delayed by up to this duration are applied. Older ones are dropped.
+    Grace(Duration),
+}
+
+/// An OHLC bar of the Lightspeed prices of a pair over an interval. Prices are in the units of
+/// the API.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candle {
+    /// The identifier of the pair (e.g., "BTC/USD").
+    pub pair: String,
+
+    /// The start of the interval, a multiple of its length since the Unix epoch.
+    pub start: Timestamp,
+
+    /// The end of the interval, excluded.
+    pub end: Timestamp,
+
+    /// The price of the earliest tick.
+    pub open: f64,
+
+    pub high: f64,
+
+    pub low: f64,
+
+    /// The price of the latest tick.
+    pub close: f64,
+
+    /// The number of ticks in the candle.
+    pub ticks: u32,
+
+    /// The lowest `num_sources_aggregated` of the ticks.
+    pub min_sources: u32,
+
+    /// The highest `num_sources_aggregated` of the ticks.
+    pub max_sources: u32,
+}
+
+/// A candle being built, with the timestamps of its open and close ticks.
+#[derive(Debug)]
+struct OpenCandle {
+    candle: Candle,
+    opened_at: Timestamp,
+    closed_at: Timestamp,
+}
+
+impl OpenCandle {
+    fn new(pair: &str, start: Timestamp, end: Timestamp, tick: Tick) -> Self {
+        Self {
+            candle: Candle {
+                pair: pair.to_string(),
+                start,
+                end,
+                open: tick.price,
+                high: tick.price,
+                low: tick.price,
+                close: tick.price,
+                ticks: 1,
+                min_sources: tick.sources,
+                max_sources: tick.sources,
+            },
+            opened_at: tick.timestamp,
+            closed_at: tick.timestamp,
+        }
+    }
+
+    fn apply(&mut self, tick: Tick) {
+        let candle = &mut self.candle;
+        if tick.timestamp < self.opened_at {
+            self.opened_at = tick.timestamp;
+            candle.open = tick.price;
+        }
+        if tick.timestamp >= self.closed_at {
+            self.closed_at = tick.timestamp;
+            candle.close = tick.price;
+        }
+        candle.high = candle.high.max(tick.price);
+        candle.low = candle.low.min(tick.price);
+        candle.ticks += 1;
+        candle.min_sources = candle.min_sources.min(tick.sources);
+        candle.max_sources = candle.max_sources.max(tick.sources);
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Tick {
+    price: f64,
+    sources: u32,
+    timestamp: Timestamp,
+}
+
+impl Tick {
+    fn new(update: &PriceUpdate, timestamp: Timestamp) -> Result<Self, PragmaError> {
+        Ok(Self {
+            price: update.to_sample(timestamp)?.price,
+            sources: update.num_sources_aggregated,
+            timestamp,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct PairCandles {
+    /// Open candles by start.
+    open: BTreeMap<Timestamp, OpenCandle>,
+    /// The latest tick timestamp received for the pair.
+    watermark: Timestamp,
+    /// Candles starting before this are closed.
+    closed_before: Timestamp,
+}
+
+/// Builds OHLC candles aligned to an `Interval` from Lightspeed price updates.
+///
+/// Candles are only emitted for intervals with at least one tick.
+///
+/// # Examples
+///
+/// ```
+/// use pragma_rs::{CandleAggregator, Interval, LateTickPolicy, LightspeedMessage, PriceUpdate, Timestamp};
+///
+/// let mut candles = CandleAggregator::new(Interval::OneMinute, LateTickPolicy::Drop);
+/// let tick = |secs: u64, price: u128| LightspeedMessage::PriceUpdate {
+///     oracle_prices: vec![PriceUpdate {
+///         num_sources_aggregated: 5,
+///         pair_id: "BTC/USD".to_string(),
+///         price: format!("{price:#x}"),
+///     }],
+///     timestamp: Timestamp::from_secs(secs),
+/// };
+///
+/// assert!(candles.push(&tick(60, 100)).unwrap().is_empty());
+/// assert!(candles.push(&tick(90, 120)).unwrap().is_empty());
+/// assert!(candles.push(&tick(100, 110)).unwrap().is_empty());
+///
+/// // The first tick of the next minute closes the candle.
+/// let closed = candles.push(&tick(125, 130)).unwrap();
+/// assert_eq!(closed.len(), 1);
+/// assert_eq!(closed[0].start, Timestamp::from_secs(60));
+/// assert_eq!((closed[0].open, closed[0].high, closed[0].low, closed[0].close), (100.0, 120.0, 100.0, 110.0));
+/// assert_eq!(closed[0].ticks, 3);
+/// ```
+#[derive(Debug)]
+pub struct CandleAggregator {
+    interval_millis: u64,
+    policy: LateTickPolicy,
+    pairs: HashMap<String, PairCandles>,
+    late_ticks: u64,
+}
+
+impl CandleAggregator {
+    pub fn new(interval: Interval, policy: LateTickPolicy) -> Self {
+        // `Interval::to_millis` counts 1400 minutes in a day.
+        let interval_millis = match interval {
+            Interval::OneDay => 24 * 60 * 60 * 1000,
+            interval => interval.to_millis(),
+        };
+        Self {
+            interval_millis: interval_millis.max(1),
+            policy,
+            pairs: HashMap::new(),
+            late_ticks: 0,
+        }
+    }
+
+    fn grace(&self) -> Duration {
+        match self.policy {
+            LateTickPolicy::Drop => Duration::ZERO,
+            LateTickPolicy::Grace(grace) => grace,
+        }
+    }
+
+    /// Adds the ticks of a `LightspeedMessage::PriceUpdate`, ignoring the other messages, and
+    /// returns the candles closed by them.
+    ///
+    /// Fails without adding any tick if one of the prices cannot be parsed.
+    pub fn push(&mut self, msg: &LightspeedMessage) -> Result<Vec<Candle>, PragmaError> {
+        let LightspeedMessage::PriceUpdate {
+            oracle_prices,
+            timestamp,
+        } = msg
+        else {
+            return Ok(Vec::new());
+        };
+
+        let ticks = oracle_prices
+            .iter()
+            .map(|update| Ok((update, Tick::new(update, *timestamp)?)))
+            .collect::<Result<Vec<_>, PragmaError>>()?;
+        Ok(ticks
+            .into_iter()
+            .flat_map(|(update, tick)| self.push_tick(&update.pair_id, tick))
+            .collect())
+    }
+
+    /// Adds a single tick of a pair, and returns the candles it closed.
+    pub fn push_update(
+        &mut self,
+        update: &PriceUpdate,
+        timestamp: Timestamp,
+    ) -> Result<Vec<Candle>, PragmaError> {
+        let tick = Tick::new(update, timestamp)?;
+        Ok(self.push_tick(&update.pair_id, tick))
+    }
+
+    fn push_tick(&mut self, pair_id: &str, tick: Tick) -> Vec<Candle> {
+        let timestamp = tick.timestamp;
+        let start = Timestamp::from_millis(
+            timestamp.as_millis() - timestamp.as_millis() % self.interval_millis,
+        );
+        let end = start + Duration::from_millis(self.interval_millis);
+        let grace = self.grace();
+
+        let pair = self.pairs.entry(pair_id.to_string()).or_default();
+        if start < pair.closed_before {
+            self.late_ticks += 1;
+            return Vec::new();
+        }
+        match pair.open.get_mut(&start) {
+            Some(candle) => candle.apply(tick),
+            None => {
+                let candle = OpenCandle::new(pair_id, start, end, tick);
+                pair.open.insert(start, candle);
+            }
+        }
+
+        pair.watermark = pair.watermark.max(timestamp);
+        let watermark = pair.watermark;
+        Self::close(pair, |end| end + grace <= watermark)
+    }
+
+    /// Closes the candles whose interval and grace period ended before `now`, e.g. on a timer
+    /// when the updates of a pair stop, and returns them.
+    pub fn close_until(&mut self, now: Timestamp) -> Vec<Candle> {
+        let grace = self.grace();
+        self.pairs
+            .values_mut()
+            .flat_map(|pair| Self::close(pair, |end| end + grace <= now))
+            .collect()
+    }
+
+    /// Closes every candle, and returns them.
+    pub fn close_all(&mut self) -> Vec<Candle> {
+        self.pairs
+            .values_mut()
+            .flat_map(|pair| Self::close(pair, |_| true))
+            .collect()
+    }
+
+    fn close(pair: &mut PairCandles, is_over: impl Fn(Timestamp) -> bool) -> Vec<Candle> {
+        let mut closed = Vec::new();
+        while let Some(entry) = pair.open.first_entry() {
+            if !is_over(entry.get().candle.end) {
+                break;
+            }
+            let candle = entry.remove().candle;
+            pair.closed_before = pair.closed_before.max(candle.end);
+            closed.push(candle);
+        }
+        closed
+    }
+
+    /// Returns the candles of `pair` being built, oldest first.
+    pub fn open_candles(&self, pair: &str) -> Vec<Candle> {
+        self.pairs
+            .get(pair)
+            .map(|pair| pair.open.values().map(|c| c.candle.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the number of ticks dropped because their candle was already closed.
+    pub fn late_ticks(&self) -> u64 {
+        self.late_ticks
+    }
+}
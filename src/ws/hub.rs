@@ -0,0 +1,215 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use tokio::{
+    sync::{broadcast, mpsc},
+    task::JoinHandle,
+};
+
+use super::{PairMessage, PragmaWsClient, WsError};
+
+/// Default number of messages a subscription of a `SubscriptionHub` can lag behind before
+/// skipping some.
+pub const DEFAULT_HUB_CAPACITY: usize = 1024;
+
+/// The subscriptions sent upstream, counted by pair.
+struct Upstream<T> {
+    sender: mpsc::UnboundedSender<T>,
+    refs: Mutex<HashMap<String, usize>>,
+}
+
+impl<T: PairMessage> Upstream<T> {
+    fn refs(&self) -> MutexGuard<'_, HashMap<String, usize>> {
+        self.refs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Counts a new consumer of `pairs`, and subscribes upstream to the pairs without one.
+    fn acquire(&self, pairs: &HashSet<String>) -> Result<(), WsError> {
+        let mut refs = self.refs();
+        let new: Vec<String> = pairs
+            .iter()
+            .filter(|pair| !refs.contains_key(*pair))
+            .cloned()
+            .collect();
+        if !new.is_empty() {
+            self.sender
+                .send(T::subscribe(new))
+                .map_err(|e| WsError::Send(e.to_string()))?;
+        }
+        for pair in pairs {
+            *refs.entry(pair.clone()).or_default() += 1;
+        }
+        Ok(())
+    }
+
+    /// Removes a consumer of `pairs`, and unsubscribes upstream from the pairs without any left.
+    fn release(&self, pairs: &HashSet<String>) {
+        let mut refs = self.refs();
+        let mut unused = Vec::new();
+        for pair in pairs {
+            if let Some(count) = refs.get_mut(pair) {
+                *count -= 1;
+                if *count == 0 {
+                    refs.remove(pair);
+                    unused.push(pair.clone());
+                }
+            }
+        }
+        if !unused.is_empty() {
+            // Fails only once the connection is closed, when there is nothing to unsubscribe from.
+            let _ = self.sender.send(T::unsubscribe(unused));
+        }
+    }
+}
+
+struct Shared<T> {
+    upstream: Arc<Upstream<T>>,
+    /// Weak so that the subscriptions are closed with the connection, when the task stops.
+    messages: broadcast::WeakSender<Arc<T>>,
+    task: JoinHandle<()>,
+}
+
+impl<T> Drop for Shared<T> {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Shares one WebSocket connection between many consumers, each receiving the updates of its own
+/// pairs. See [`PragmaWsClient::hub`].
+///
+/// The hub subscribes upstream to a pair when its first consumer subscribes to it, and
+/// unsubscribes once the last one is dropped. Cloning the hub shares the connection, which is
+/// closed when the hub and its clones are dropped.
+pub struct SubscriptionHub<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Clone for SubscriptionHub<T> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T: PairMessage> SubscriptionHub<T> {
+    /// Subscribes to the updates of `pairs`, sending the subscription upstream for the pairs no
+    /// other consumer subscribed to.
+    pub fn subscribe(&self, pairs: Vec<String>) -> Result<HubSubscription<T>, WsError> {
+        let pairs: HashSet<String> = pairs.into_iter().collect();
+        let upstream = self.shared.upstream.clone();
+        // The receiver exists before the upstream subscribe, so the first updates are not lost.
+        let receiver = match self.shared.messages.upgrade() {
+            Some(sender) => sender.subscribe(),
+            // The connection is closed, the subscription is closed as well.
+            None => broadcast::channel(1).1,
+        };
+        upstream.acquire(&pairs)?;
+        Ok(HubSubscription {
+            pairs,
+            receiver,
+            upstream,
+        })
+    }
+
+    /// Returns the pairs subscribed to upstream, with their number of consumers.
+    pub fn subscriptions(&self) -> HashMap<String, usize> {
+        self.shared.upstream.refs().clone()
+    }
+}
+
+/// The updates of a set of pairs, received from a [`SubscriptionHub`]. Dropping it releases its
+/// pairs.
+pub struct HubSubscription<T: PairMessage> {
+    pairs: HashSet<String>,
+    receiver: broadcast::Receiver<Arc<T>>,
+    upstream: Arc<Upstream<T>>,
+}
+
+impl<T: PairMessage> Drop for HubSubscription<T> {
+    fn drop(&mut self) {
+        self.upstream.release(&self.pairs);
+    }
+}
+
+impl<T: PairMessage> HubSubscription<T> {
+    /// Receives the next message with updates of the pairs of the subscription, restricted to
+    /// them. Returns `WsError::Lagged` if the subscription fell too far behind and skipped
+    /// messages, and `None` once the connection is closed.
+    pub async fn recv(&mut self) -> Option<Result<T, WsError>> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(msg) => {
+                    if let Some(msg) = msg.retain(&self.pairs) {
+                        return Some(Ok(msg));
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    return Some(Err(WsError::Lagged(skipped)))
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// Returns the pairs of the subscription.
+    pub fn pairs(&self) -> impl Iterator<Item = &str> {
+        self.pairs.iter().map(String::as_str)
+    }
+}
+
+impl<T: PairMessage> PragmaWsClient<T> {
+    /// Turns a connected client into a hub sharing its connection between many consumers. Each
+    /// subscription buffers up to `capacity` messages, e.g. `DEFAULT_HUB_CAPACITY`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pragma_rs::{Config, Environment, PragmaClient, DEFAULT_HUB_CAPACITY};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let config = Config::new("your_api_key".to_string(), Environment::Development);
+    ///     let client = PragmaClient::new(config)?;
+    ///     let mut ws_client = client.lightspeed_ws_client();
+    ///     ws_client.connect().await?;
+    ///     let hub = ws_client.hub(DEFAULT_HUB_CAPACITY);
+    ///
+    ///     for pair in ["BTC/USD", "ETH/USD"] {
+    ///         let mut updates = hub.subscribe(vec![pair.to_string()])?;
+    ///         tokio::spawn(async move {
+    ///             while let Some(Ok(msg)) = updates.recv().await {
+    ///                 println!("{msg:?}");
+    ///             }
+    ///         });
+    ///     }
+    ///     tokio::signal::ctrl_c().await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn hub(mut self, capacity: usize) -> SubscriptionHub<T> {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        let messages = sender.downgrade();
+        let upstream = Arc::new(Upstream {
+            sender: self.sender(),
+            refs: Mutex::new(HashMap::new()),
+        });
+        let task = tokio::spawn(async move {
+            while let Some(msg) = self.recv().await {
+                // Fails when no subscription is alive, the message is then for nobody.
+                let _ = sender.send(Arc::new(msg));
+            }
+        });
+
+        SubscriptionHub {
+            shared: Arc::new(Shared {
+                upstream,
+                messages,
+                task,
+            }),
+        }
+    }
+}
@@ -1,9 +1,11 @@
-use crate::PragmaClient;
+use std::collections::HashSet;
+
+use crate::{telemetry, PragmaClient, Timestamp};
 use serde::{Deserialize, Serialize};
 
-use super::PragmaWsClient;
+use super::{PairMessage, PragmaWsClient, Timestamped};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum LightspeedMessage {
     Subscribe {
@@ -16,11 +18,11 @@ pub enum LightspeedMessage {
     },
     PriceUpdate {
         oracle_prices: Vec<PriceUpdate>,
-        timestamp: i64,
+        timestamp: Timestamp,
     },
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PriceUpdate {
     /// The number of sources aggregated to compute the price.
     pub num_sources_aggregated: u32,
@@ -32,16 +34,99 @@ pub struct PriceUpdate {
     pub price: String,
 }
 
+impl Timestamped for LightspeedMessage {
+    fn timestamp(&self) -> Option<Timestamp> {
+        match self {
+            Self::PriceUpdate { timestamp, .. } => Some(*timestamp),
+            _ => None,
+        }
+    }
+}
+
+impl PairMessage for LightspeedMessage {
+    fn subscribe(pairs: Vec<String>) -> Self {
+        Self::Subscribe {
+            msg_type: "subscribe".to_string(),
+            pairs,
+        }
+    }
+
+    fn unsubscribe(pairs: Vec<String>) -> Self {
+        Self::Unsubscribe {
+            msg_type: "unsubscribe".to_string(),
+            pairs,
+        }
+    }
+
+    fn subscribed_pairs(&self) -> Option<&[String]> {
+        match self {
+            Self::Subscribe { pairs, .. } => Some(pairs),
+            _ => None,
+        }
+    }
+
+    fn unsubscribed_pairs(&self) -> Option<&[String]> {
+        match self {
+            Self::Unsubscribe { pairs, .. } => Some(pairs),
+            _ => None,
+        }
+    }
+
+    fn prices(&self) -> Vec<(String, String)> {
+        match self {
+            Self::PriceUpdate { oracle_prices, .. } => oracle_prices
+                .iter()
+                .map(|u| (u.pair_id.clone(), u.price.clone()))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn retain(&self, pairs: &HashSet<String>) -> Option<Self> {
+        let Self::PriceUpdate {
+            oracle_prices,
+            timestamp,
+        } = self
+        else {
+            return None;
+        };
+        let oracle_prices: Vec<PriceUpdate> = oracle_prices
+            .iter()
+            .filter(|u| pairs.contains(&u.pair_id))
+            .cloned()
+            .collect();
+        (!oracle_prices.is_empty()).then_some(Self::PriceUpdate {
+            oracle_prices,
+            timestamp: *timestamp,
+        })
+    }
+}
+
+/// The path of the endpoint, appended to the WebSocket URL of the environment.
+const PATH: &str = "/node/v1/data/price/subscribe";
+
 impl PragmaClient {
     /// Creates a WebSocket client for the Lightspeed endpoint.
     ///
     /// This method configures a `PragmaWsClient` to connect to the Lightspeed WebSocket endpoint,
     /// which provides ultra-fast price updates every 500ms without verification metadata.
     pub fn lightspeed_ws_client(&self) -> PragmaWsClient<LightspeedMessage> {
-        let url = format!("{}/node/v1/data/price/subscribe", self.config.ws_url);
-        let api_key = self.config.api_key.clone();
-        PragmaWsClient::new(url, api_key, |msg| {
-            serde_json::from_str::<LightspeedMessage>(&msg).ok()
+        let url = format!("{}{PATH}", self.config.ws_url);
+        PragmaWsClient::with_api_key(url, self.config.api_key.clone(), |msg| {
+            let msg = serde_json::from_str::<LightspeedMessage>(&msg).ok()?;
+            if let LightspeedMessage::PriceUpdate {
+                oracle_prices,
+                timestamp,
+            } = &msg
+            {
+                for update in oracle_prices {
+                    telemetry::ws_price_update("lightspeed", &update.pair_id, *timestamp);
+                }
+            }
+            Some(msg)
         })
+        .with_cassette(self.recorder.clone(), self.replayer.clone())
+        .with_clock(self.config.clock.clone())
+        .with_endpoints(self, PATH)
     }
 }
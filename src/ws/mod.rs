@@ -1,18 +1,70 @@
+pub(crate) mod candles;
+pub(crate) mod hub;
 pub(crate) mod lightspeed;
+pub(crate) mod price_book;
+pub(crate) mod rolling;
 pub(crate) mod starkex;
 
+use std::collections::{BTreeSet, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
 
 use futures_util::{SinkExt, StreamExt};
 use serde::Serialize;
 use thiserror::Error;
+use tokio::net::TcpStream;
 use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 use tokio_tungstenite::tungstenite::client::IntoClientRequest;
-use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
+use tokio_tungstenite::{
+    connect_async,
+    tungstenite::{self, protocol::Message},
+    MaybeTlsStream, WebSocketStream,
+};
+
+use crate::api_key::SharedApiKey;
+use crate::cassette::{cassette_url, CassetteEntry, Recorder, ReplayTiming, Replayer};
+use crate::clock::SharedClock;
+use crate::failover::Endpoints;
+use crate::telemetry::{self, trace_event};
+use crate::{ApiKey, PragmaClient, PragmaError, Timestamp};
 
 const PING_INTERVAL: Duration = Duration::from_secs(25);
 
+/// The shortest period between two checks of the active endpoint, to fail back to it.
+const MIN_FAILBACK_CHECK: Duration = Duration::from_millis(100);
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+type MessageHandler<T> = Arc<dyn Fn(String) -> Option<T> + Send + Sync>;
+
+type Probe = Arc<dyn Fn() + Send + Sync>;
+
+/// How a [`PragmaWsClient`] reconnects after losing its connection, see
+/// [`PragmaWsClient::with_reconnect`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReconnectConfig {
+    /// The delay before the first attempt, doubled after each failed attempt.
+    pub initial_backoff: Duration,
+
+    /// The longest delay between two attempts.
+    pub max_backoff: Duration,
+
+    /// The number of failed attempts in a row after which the client closes, or `None` to try
+    /// forever.
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            max_attempts: None,
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum WsError {
     #[error("Connection error: {0}")]
@@ -25,21 +77,85 @@ pub enum WsError {
     Serialization(String),
     #[error("Could not insert API key to header: {0}")]
     InvalidApiKey(#[from] reqwest::header::InvalidHeaderValue),
+    #[error("Lagged behind the stream: {0} messages skipped")]
+    Lagged(u64),
+    #[error("Handshake rejected with status {0}")]
+    Rejected(u16),
+}
+
+impl WsError {
+    /// Whether the error is caused by the endpoint rather than by the request, e.g. its API key.
+    fn is_endpoint_failure(&self) -> bool {
+        match self {
+            Self::Connection(_) => true,
+            Self::Rejected(status) => *status >= 500,
+            _ => false,
+        }
+    }
+}
+
+/// A WebSocket message which may carry prices.
+pub trait Timestamped {
+    /// Returns the publication time of the prices of the message, or `None` if it carries no prices.
+    fn timestamp(&self) -> Option<Timestamp>;
+}
+
+/// The messages of a stream whose prices are subscribed to by pair, e.g. "BTC/USD".
+pub trait PairMessage: Timestamped + Serialize + Clone + Send + Sync + 'static {
+    /// Returns the message subscribing to `pairs`.
+    fn subscribe(pairs: Vec<String>) -> Self;
+
+    /// Returns the message unsubscribing from `pairs`.
+    fn unsubscribe(pairs: Vec<String>) -> Self;
+
+    /// Returns the pairs of the message if it subscribes to them.
+    fn subscribed_pairs(&self) -> Option<&[String]>;
+
+    /// Returns the pairs of the message if it unsubscribes from them.
+    fn unsubscribed_pairs(&self) -> Option<&[String]>;
+
+    /// Returns the `(pair, price)` of the updates of the message.
+    fn prices(&self) -> Vec<(String, String)>;
+
+    /// Returns the message restricted to the updates of `pairs`, or `None` if it has none.
+    fn retain(&self, pairs: &HashSet<String>) -> Option<Self>;
 }
 
 pub struct PragmaWsClient<T> {
     url: String,
-    api_key: String,
+    /// Name of the stream, used to label metrics.
+    stream: &'static str,
+    api_key: SharedApiKey,
     outgoing_sender: mpsc::UnboundedSender<T>,
     outgoing_receiver: Option<mpsc::UnboundedReceiver<T>>,
-    incoming_sender: mpsc::UnboundedSender<T>,
+    /// Taken by the task feeding the incoming channel, so that it closes with the connection.
+    incoming_sender: Option<mpsc::UnboundedSender<T>>,
     incoming_receiver: mpsc::UnboundedReceiver<T>,
-    message_handler: Arc<dyn Fn(String) -> Option<T> + Send + Sync>,
+    message_handler: MessageHandler<T>,
+    recorder: Option<Recorder>,
+    replayer: Option<Arc<Replayer>>,
+    clock: SharedClock,
+    max_age: Option<Duration>,
+    /// The task owning the connection, started by `connect`.
+    task: Option<JoinHandle<()>>,
+    /// The endpoints of the `PragmaClient` and the path of the stream, to connect to the active one.
+    endpoints: Option<(Arc<Endpoints>, &'static str)>,
+    /// Probes the endpoints of the `PragmaClient` when they are due, to fail back to them.
+    probe: Option<Probe>,
+    reconnect: Option<(ReconnectConfig, Resubscribe<T>)>,
 }
 
 impl<T: Send + 'static + Serialize> PragmaWsClient<T> {
     /// Creates a new WebSocket client with separate channels for sending and receiving.
-    pub fn new<F>(url: String, api_key: String, message_handler: F) -> Self
+    pub fn new<F>(url: String, api_key: impl Into<ApiKey>, message_handler: F) -> Self
+    where
+        F: Fn(String) -> Option<T> + Send + Sync + 'static,
+    {
+        Self::with_api_key(url, SharedApiKey::new(api_key.into()), message_handler)
+    }
+
+    /// Creates a client reading its API key from the provider of a `Config` when connecting.
+    pub(crate) fn with_api_key<F>(url: String, api_key: SharedApiKey, message_handler: F) -> Self
     where
         F: Fn(String) -> Option<T> + Send + Sync + 'static,
     {
@@ -48,86 +164,145 @@ impl<T: Send + 'static + Serialize> PragmaWsClient<T> {
         // Channel for incoming messages (WebSocket -> user)
         let (incoming_sender, incoming_receiver) = mpsc::unbounded_channel::<T>();
 
+        let stream =
+            reqwest::Url::parse(&url).map_or("other", |url| telemetry::ws_stream(url.path()));
+
         Self {
             url,
+            stream,
             api_key,
             outgoing_sender,
             outgoing_receiver: Some(outgoing_receiver),
-            incoming_sender,
+            incoming_sender: Some(incoming_sender),
             incoming_receiver,
             message_handler: Arc::new(message_handler),
+            recorder: None,
+            replayer: None,
+            clock: SharedClock::default(),
+            max_age: None,
+            task: None,
+            endpoints: None,
+            probe: None,
+            reconnect: None,
         }
     }
 
-    /// Connects to the WebSocket and starts processing messages in separate tasks.
-    pub async fn connect(&mut self) -> Result<(), WsError> {
-        let url = self.url.clone();
-        let message_handler = self.message_handler.clone();
-        let api_key = self.api_key.clone();
+    /// Records the traffic to, or replays it from, the cassette of the `PragmaClient`.
+    pub(crate) fn with_cassette(
+        mut self,
+        recorder: Option<Recorder>,
+        replayer: Option<Arc<Replayer>>,
+    ) -> Self {
+        self.recorder = recorder;
+        self.replayer = replayer;
+        self
+    }
 
-        let mut request = match url.into_client_request() {
-            Ok(r) => Ok(r),
-            Err(e) => Err(WsError::Connection(format!("{e}"))),
-        }?;
+    /// Uses the clock of the `PragmaClient` to check the freshness of the updates.
+    pub(crate) fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
 
-        request.headers_mut().insert("x-api-key", api_key.parse()?);
+    /// Returns the path of the endpoint, used to identify its frames in a cassette.
+    fn cassette_url(&self) -> String {
+        reqwest::Url::parse(&self.url)
+            .map(|url| cassette_url(&url))
+            .unwrap_or_else(|_| self.url.clone())
+    }
 
-        let (ws_stream, _) = connect_async(request)
-            .await
-            .map_err(|e| WsError::Connection(e.to_string()))?;
+    /// Connects to the WebSocket and starts processing messages in a separate task.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "pragma_ws",
+            skip_all,
+            fields(stream = self.stream, url = %self.cassette_url())
+        )
+    )]
+    pub async fn connect(&mut self) -> Result<(), WsError> {
+        if let Some(replayer) = self.replayer.clone() {
+            return self.replay(&replayer);
+        }
+        if self.outgoing_receiver.is_none() || self.incoming_sender.is_none() {
+            return Err(WsError::Send("Connect already called.".into()));
+        }
+
+        let connector = Connector {
+            url: self.url.clone(),
+            api_key: self.api_key.clone(),
+            endpoints: self.endpoints.clone(),
+        };
+        let (ws_stream, index) = connector.open().await?;
+        trace_event!(info, "connected");
+        telemetry::ws_connected(self.stream);
 
-        let (mut write, mut read) = ws_stream.split();
+        let (Some(outgoing), Some(incoming)) =
+            (self.outgoing_receiver.take(), self.incoming_sender.take())
+        else {
+            return Err(WsError::Send("Connect already called.".into()));
+        };
+        let connection = Connection {
+            connector,
+            stream: self.stream,
+            outgoing,
+            incoming,
+            message_handler: self.message_handler.clone(),
+            recorder: self.recorder.clone().map(|r| (r, self.cassette_url())),
+            reconnect: self.reconnect,
+            probe: self.probe.clone(),
+            pairs: BTreeSet::new(),
+        };
+        let task = connection.run(ws_stream, index);
 
-        let incoming_sender = self.incoming_sender.clone();
+        #[cfg(feature = "tracing")]
+        let task = tracing::Instrument::in_current_span(task);
+        self.task = Some(tokio::spawn(task));
 
-        let Some(mut outgoing_receiver) = self.outgoing_receiver.take() else {
+        Ok(())
+    }
+
+    /// Feeds the frames recorded in the cassette to the incoming channel, instead of connecting.
+    fn replay(&mut self, replayer: &Replayer) -> Result<(), WsError> {
+        let (Some(mut outgoing_receiver), Some(incoming_sender)) =
+            (self.outgoing_receiver.take(), self.incoming_sender.take())
+        else {
             return Err(WsError::Send("Connect already called.".into()));
         };
 
-        tokio::spawn(async move {
-            let mut ping_interval = tokio::time::interval(PING_INTERVAL);
-            loop {
-                tokio::select! {
-                    // Handle outgoing user messages
-                    msg = outgoing_receiver.recv() => {
-                        match msg {
-                            Some(msg) => {
-                                if let Ok(json) = serde_json::to_string(&msg) {
-                                    if write.send(Message::Text(json.into())).await.is_err() {
-                                        break;
-                                    }
-                                }
-                            }
-                            None => {
-                                break;
-                            }
-                        }
-                    }
-                    // Handle periodic pings
-                    _ = ping_interval.tick() => {
-                        if write.send(Message::Ping(Default::default())).await.is_err() {
-                            break;
-                        }
-                    }
+        let frames = replayer.ws_frames(&self.cassette_url());
+        let timing = replayer.timing;
+        let message_handler = self.message_handler.clone();
+        let stream = self.stream;
+
+        trace_event!(info, frames = frames.len(), "replaying cassette");
+
+        // Outgoing messages are accepted and dropped: the replayed frames do not depend on them.
+        tokio::spawn(async move { while outgoing_receiver.recv().await.is_some() {} });
+
+        let replay = async move {
+            let mut previous: Option<Timestamp> = None;
+            for (at, frame) in frames {
+                if let (ReplayTiming::Original, Some(previous)) = (timing, previous) {
+                    tokio::time::sleep(at.saturating_duration_since(previous)).await;
                 }
-            }
-        });
-
-        tokio::spawn(async move {
-            while let Some(message) = read.next().await {
-                match message {
-                    Ok(Message::Text(text)) => {
-                        if let Some(parsed) = message_handler(text.to_string()) {
-                            let _ = incoming_sender.send(parsed);
-                        }
-                    }
-                    Ok(_) => {}
-                    Err(_) => {
-                        break;
-                    }
+                previous = Some(at);
+                let parsed = message_handler(frame);
+                telemetry::ws_message(stream, parsed.is_some());
+                let Some(parsed) = parsed else {
+                    trace_event!(warn, "failed to parse replayed message");
+                    continue;
+                };
+                if incoming_sender.send(parsed).is_err() {
+                    break;
                 }
+                telemetry::ws_queue(stream, 1.0);
             }
-        });
+        };
+
+        #[cfg(feature = "tracing")]
+        let replay = tracing::Instrument::in_current_span(replay);
+        tokio::spawn(replay);
 
         Ok(())
     }
@@ -140,8 +315,385 @@ impl<T: Send + 'static + Serialize> PragmaWsClient<T> {
         Ok(())
     }
 
-    /// Receives the next parsed message from the WebSocket.
+    /// Returns a sender of messages to the WebSocket, for the tasks not owning the client.
+    pub(crate) fn sender(&self) -> mpsc::UnboundedSender<T> {
+        self.outgoing_sender.clone()
+    }
+
+    /// Receives the next parsed message from the WebSocket, or `None` once the connection is
+    /// closed and the received messages are consumed.
     pub async fn recv(&mut self) -> Option<T> {
-        self.incoming_receiver.recv().await
+        let msg = self.incoming_receiver.recv().await;
+        if msg.is_some() {
+            telemetry::ws_queue(self.stream, -1.0);
+        }
+        msg
+    }
+
+    /// Closes the connection, waiting for the queued messages and the close frame to be sent.
+    /// Dropping the client closes it too, without waiting.
+    pub async fn close(mut self) {
+        // The connection task closes the connection once nothing can receive its messages.
+        self.outgoing_sender = mpsc::unbounded_channel().0;
+        self.incoming_receiver.close();
+        if let Some(task) = self.task.take() {
+            let _ = task.await;
+        }
+    }
+}
+
+impl<T> Drop for PragmaWsClient<T> {
+    fn drop(&mut self) {
+        // Closing first stops the connection task from queueing more messages while they are
+        // counted.
+        self.incoming_receiver.close();
+        telemetry::ws_queue(self.stream, -(self.incoming_receiver.len() as f64));
+    }
+}
+
+impl<T: Timestamped + Send + 'static + Serialize> PragmaWsClient<T> {
+    /// Flags the price updates older than `max_age` in [`Self::recv_fresh`].
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Receives the next parsed message, or `PragmaError::StalePrice` if it carries prices older
+    /// than the `max_age` of the client. Messages without prices are never stale.
+    pub async fn recv_fresh(&mut self) -> Option<Result<T, PragmaError>> {
+        let msg = self.recv().await?;
+        let fresh = match msg.timestamp() {
+            Some(timestamp) => self.clock.check_freshness(timestamp, self.max_age),
+            None => Ok(()),
+        };
+        Some(fresh.map(|()| msg))
+    }
+}
+
+impl<T: PairMessage> PragmaWsClient<T> {
+    /// Reconnects with `reconnect` when the connection is lost, instead of closing the client. The
+    /// new connection subscribes again to the pairs the client was subscribed to.
+    ///
+    /// Clients created by a `PragmaClient` reconnect to its active endpoint, see
+    /// [`crate::Config::with_ws_reconnect`].
+    pub fn with_reconnect(mut self, reconnect: ReconnectConfig) -> Self {
+        let resubscribe = Resubscribe {
+            track: track_subscriptions::<T>,
+            subscribe: T::subscribe,
+        };
+        self.reconnect = Some((reconnect, resubscribe));
+        self
+    }
+
+    /// Connects to the `path` of the active endpoint of `client`, instead of the URL of the
+    /// client, and reconnects according to its `Config`.
+    pub(crate) fn with_endpoints(mut self, client: &PragmaClient, path: &'static str) -> Self {
+        self.endpoints = Some((client.endpoints.clone(), path));
+        let probing = client.clone();
+        self.probe = Some(Arc::new(move || probing.probe_endpoints()));
+        match client.config.ws_reconnect() {
+            Some(reconnect) => self.with_reconnect(reconnect),
+            None => self,
+        }
+    }
+}
+
+/// Tracks the pairs subscribed to by the messages sent, to subscribe to them again after a
+/// reconnection.
+struct Resubscribe<T> {
+    track: fn(&T, &mut BTreeSet<String>),
+    subscribe: fn(Vec<String>) -> T,
+}
+
+impl<T> Clone for Resubscribe<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Resubscribe<T> {}
+
+fn track_subscriptions<T: PairMessage>(msg: &T, pairs: &mut BTreeSet<String>) {
+    if let Some(subscribed) = msg.subscribed_pairs() {
+        pairs.extend(subscribed.iter().cloned());
+    }
+    if let Some(unsubscribed) = msg.unsubscribed_pairs() {
+        for pair in unsubscribed {
+            pairs.remove(pair);
+        }
+    }
+}
+
+/// Opens the connections of a client, to its URL or to the active endpoint of its `PragmaClient`.
+struct Connector {
+    url: String,
+    api_key: SharedApiKey,
+    endpoints: Option<(Arc<Endpoints>, &'static str)>,
+}
+
+impl Connector {
+    /// Opens a connection, failing over to the next endpoints if the client has fallbacks, and
+    /// returns it with the index of its endpoint.
+    ///
+    /// Only transport errors and `5xx` handshake responses fail over: a rejected API key would be
+    /// rejected by every endpoint.
+    async fn open(&self) -> Result<(WsStream, Option<usize>), WsError> {
+        let Some((endpoints, path)) = &self.endpoints else {
+            return Ok((self.open_url(self.url.clone()).await?, None));
+        };
+        let mut attempts = endpoints.len();
+        loop {
+            let (index, endpoint) = endpoints.active();
+            match self.open_url(format!("{}{path}", endpoint.ws_url)).await {
+                Ok(ws_stream) => return Ok((ws_stream, Some(index))),
+                Err(e) if e.is_endpoint_failure() => {
+                    endpoints.fail(index);
+                    if attempts == 1 {
+                        return Err(e);
+                    }
+                    attempts -= 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    async fn open_url(&self, url: String) -> Result<WsStream, WsError> {
+        let api_key = self.api_key.get().header_value().map_err(|e| match e {
+            PragmaError::InvalidHeader(e) => WsError::InvalidApiKey(e),
+            e => WsError::Connection(e.to_string()),
+        })?;
+
+        let mut request = match url.into_client_request() {
+            Ok(r) => Ok(r),
+            Err(e) => Err(WsError::Connection(format!("{e}"))),
+        }?;
+
+        request.headers_mut().insert("x-api-key", api_key);
+
+        match connect_async(request).await {
+            Ok((ws_stream, _)) => Ok(ws_stream),
+            Err(e) => {
+                trace_event!(warn, error = %e, "connection failed");
+                match e {
+                    tungstenite::Error::Http(response) => {
+                        Err(WsError::Rejected(response.status().as_u16()))
+                    }
+                    e => Err(WsError::Connection(e.to_string())),
+                }
+            }
+        }
+    }
+
+    /// Fails over from the endpoint `index` after its connection was lost.
+    fn lost(&self, index: Option<usize>) {
+        if let (Some((endpoints, _)), Some(index)) = (&self.endpoints, index) {
+            endpoints.fail(index);
+        }
+    }
+
+    /// Whether the endpoint `index` is no longer the active one, e.g. after a failback.
+    fn moved(&self, index: Option<usize>) -> bool {
+        match (&self.endpoints, index) {
+            (Some((endpoints, _)), Some(index)) => endpoints.active().0 != index,
+            _ => false,
+        }
+    }
+
+    /// The period between two checks of the active endpoint, or `None` without fallbacks.
+    fn failback_check(&self) -> Option<Duration> {
+        let (endpoints, _) = self.endpoints.as_ref().filter(|(e, _)| e.len() > 1)?;
+        Some(endpoints.config.probe_interval.max(MIN_FAILBACK_CHECK))
+    }
+}
+
+/// Why a connection ended.
+enum Closed {
+    /// The client was closed or dropped.
+    Client,
+    /// The connection failed, or was closed by the server.
+    Lost,
+    /// The client failed back to a preferred endpoint.
+    FailBack,
+}
+
+/// The task owning the connection of a client: it sends the outgoing messages, queues the
+/// incoming ones, and reconnects if the client is configured to.
+struct Connection<T> {
+    connector: Connector,
+    stream: &'static str,
+    outgoing: mpsc::UnboundedReceiver<T>,
+    incoming: mpsc::UnboundedSender<T>,
+    message_handler: MessageHandler<T>,
+    recorder: Option<(Recorder, String)>,
+    reconnect: Option<(ReconnectConfig, Resubscribe<T>)>,
+    probe: Option<Probe>,
+    /// The pairs subscribed to, sent again after a reconnection.
+    pairs: BTreeSet<String>,
+}
+
+impl<T: Send + 'static + Serialize> Connection<T> {
+    async fn run(mut self, mut ws_stream: WsStream, mut index: Option<usize>) {
+        loop {
+            let closed = self.serve(&mut ws_stream, index).await;
+            trace_event!(info, "connection closed");
+            telemetry::ws_disconnected(self.stream);
+            match closed {
+                Closed::Client => return,
+                Closed::Lost => self.connector.lost(index),
+                Closed::FailBack => {}
+            }
+
+            let Some((reconnected, reconnected_index)) = self.reconnect().await else {
+                return;
+            };
+            ws_stream = reconnected;
+            index = reconnected_index;
+            telemetry::ws_connected(self.stream);
+            telemetry::ws_reconnected(self.stream);
+
+            if let Some((_, resubscribe)) = self.reconnect {
+                if !self.pairs.is_empty() {
+                    let msg = (resubscribe.subscribe)(self.pairs.iter().cloned().collect());
+                    // A failure is noticed, and handled, by `serve`.
+                    let _ = self.send(&mut ws_stream, msg).await;
+                }
+            }
+        }
+    }
+
+    /// Serves the connection until it is closed.
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    async fn serve(&mut self, ws_stream: &mut WsStream, index: Option<usize>) -> Closed {
+        let mut ping_interval = tokio::time::interval(PING_INTERVAL);
+        let failback_check = self
+            .connector
+            .failback_check()
+            .filter(|_| self.reconnect.is_some());
+        let mut failback_interval = tokio::time::interval(failback_check.unwrap_or(PING_INTERVAL));
+        loop {
+            tokio::select! {
+                // Queued messages are sent before the connection is closed.
+                biased;
+                msg = self.outgoing.recv() => {
+                    let Some(msg) = msg else {
+                        trace_event!(info, "client dropped, closing the connection");
+                        let _ = ws_stream.close(None).await;
+                        return Closed::Client;
+                    };
+                    if let Err(e) = self.send(ws_stream, msg).await {
+                        trace_event!(warn, error = %e, "send failed, closing the connection");
+                        return Closed::Lost;
+                    }
+                }
+                _ = self.incoming.closed() => {
+                    trace_event!(info, "client dropped, closing the connection");
+                    let _ = ws_stream.close(None).await;
+                    return Closed::Client;
+                }
+                _ = ping_interval.tick() => {
+                    if ws_stream.send(Message::Ping(Default::default())).await.is_err() {
+                        trace_event!(warn, "ping failed, closing the connection");
+                        return Closed::Lost;
+                    }
+                }
+                _ = failback_interval.tick(), if failback_check.is_some() => {
+                    if let Some(probe) = &self.probe {
+                        probe();
+                    }
+                    if self.connector.moved(index) {
+                        trace_event!(info, "moving the connection to the active endpoint");
+                        let _ = ws_stream.close(None).await;
+                        return Closed::FailBack;
+                    }
+                }
+                message = ws_stream.next() => match message {
+                    Some(Ok(Message::Text(text))) => self.receive(text.to_string()),
+                    Some(Ok(Message::Close(frame))) => {
+                        trace_event!(info, frame = ?frame, "connection closed by the server");
+                        return Closed::Lost;
+                    }
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => {
+                        trace_event!(warn, error = %e, "connection lost");
+                        return Closed::Lost;
+                    }
+                    None => return Closed::Lost,
+                },
+            }
+        }
+    }
+
+    /// Sends `msg`, tracking the pairs it subscribes to.
+    async fn send(&mut self, ws_stream: &mut WsStream, msg: T) -> Result<(), tungstenite::Error> {
+        if let Some((_, resubscribe)) = &self.reconnect {
+            (resubscribe.track)(&msg, &mut self.pairs);
+        }
+        let Ok(json) = serde_json::to_string(&msg) else {
+            return Ok(());
+        };
+        if let Some((recorder, url)) = &self.recorder {
+            recorder.record(&CassetteEntry::WsSent {
+                at: Timestamp::now(),
+                url: url.clone(),
+                frame: json.clone(),
+            });
+        }
+        trace_event!(debug, frame = %json, "message sent");
+        ws_stream.send(Message::Text(json.into())).await
+    }
+
+    /// Parses a received frame and queues it for the client.
+    fn receive(&self, text: String) {
+        if let Some((recorder, url)) = &self.recorder {
+            recorder.record(&CassetteEntry::WsReceived {
+                at: Timestamp::now(),
+                url: url.clone(),
+                frame: text.clone(),
+            });
+        }
+        let parsed = (self.message_handler)(text.clone());
+        telemetry::ws_message(self.stream, parsed.is_some());
+        match parsed {
+            Some(parsed) => {
+                if self.incoming.send(parsed).is_ok() {
+                    telemetry::ws_queue(self.stream, 1.0);
+                }
+            }
+            None => {
+                trace_event!(warn, frame = %text, "failed to parse message");
+            }
+        }
+    }
+
+    /// Opens a new connection with backoff, or returns `None` if the client does not reconnect,
+    /// gave up, or was dropped meanwhile.
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    async fn reconnect(&self) -> Option<(WsStream, Option<usize>)> {
+        let (config, _) = self.reconnect.as_ref()?;
+        let mut backoff = config.initial_backoff;
+        let mut attempts = 0u32;
+        trace_event!(info, backoff = ?backoff, "reconnecting");
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(backoff) => {}
+                _ = self.incoming.closed() => return None,
+            }
+            attempts += 1;
+            match self.connector.open().await {
+                Ok(connection) => {
+                    trace_event!(info, attempts, "reconnected");
+                    return Some(connection);
+                }
+                Err(e) => {
+                    trace_event!(warn, error = %e, attempts, "reconnection failed");
+                }
+            }
+            if config.max_attempts.is_some_and(|max| attempts >= max) {
+                trace_event!(warn, attempts, "giving up reconnecting, closing the client");
+                return None;
+            }
+            backoff = backoff.saturating_mul(2).min(config.max_backoff);
+        }
     }
 }
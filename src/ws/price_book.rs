@@ -0,0 +1,211 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use tokio::{sync::watch, task::JoinHandle};
+
+use crate::{PragmaClient, PragmaError, Timestamp};
+
+use super::{PairMessage, PragmaWsClient, WsError};
+
+/// Default age after which a `PriceBook` considers a WebSocket price stale.
+pub const DEFAULT_PRICE_BOOK_MAX_AGE: Duration = Duration::from_secs(5);
+
+/// Where the price of a `PriceBook` comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriceOrigin {
+    WebSocket,
+    Http,
+}
+
+/// The latest price of a pair.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookPrice {
+    /// The identifier of the pair, as sent by the stream.
+    pub pair: String,
+
+    /// The price, as returned by the API.
+    pub price: String,
+
+    /// The timestamp of the price.
+    pub timestamp: Timestamp,
+
+    /// When the price was received, according to the clock of the client.
+    pub received_at: Timestamp,
+
+    pub origin: PriceOrigin,
+}
+
+type Prices = RwLock<HashMap<String, watch::Sender<Option<BookPrice>>>>;
+
+/// The latest price of every pair of a Lightspeed or StarkEx stream, for the tasks which only need
+/// the current price of a pair rather than a stream of updates.
+///
+/// # Examples
+///
+/// ```no_run
+/// use pragma_rs::{Config, Environment, PragmaClient, PriceBook};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let config = Config::new("your_api_key".to_string(), Environment::Development);
+///     let client = PragmaClient::new(config)?;
+///     let book = PriceBook::lightspeed(&client, vec!["BTC/USD".to_string()]).await?;
+///
+///     // The latest WebSocket price if it is fresh, otherwise the price of `get_entry`.
+///     let price = book.get("BTC/USD").await?;
+///     println!("BTC/USD: {} ({:?})", price.price, price.origin);
+///
+///     let mut updates = book.watch("BTC/USD");
+///     while updates.changed().await.is_ok() {
+///         println!("{:?}", *updates.borrow());
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct PriceBook {
+    client: PragmaClient,
+    prices: Arc<Prices>,
+    max_age: Duration,
+    task: JoinHandle<()>,
+}
+
+impl Drop for PriceBook {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl PriceBook {
+    /// Connects a Lightspeed client of `client` and subscribes to `pairs` (e.g. "BTC/USD").
+    pub async fn lightspeed(client: &PragmaClient, pairs: Vec<String>) -> Result<Self, WsError> {
+        Self::start(client, client.lightspeed_ws_client(), pairs).await
+    }
+
+    /// Connects a StarkEx client of `client` and subscribes to `pairs`. Prices are stored by
+    /// pair, e.g. "BTC/USD", decoded from the global asset id, or by the id itself if it does not
+    /// decode to one.
+    pub async fn starkex(client: &PragmaClient, pairs: Vec<String>) -> Result<Self, WsError> {
+        Self::start(client, client.starkex_ws_client(), pairs).await
+    }
+
+    async fn start<T: PairMessage>(
+        client: &PragmaClient,
+        mut ws_client: PragmaWsClient<T>,
+        pairs: Vec<String>,
+    ) -> Result<Self, WsError> {
+        ws_client.connect().await?;
+        ws_client.send(T::subscribe(pairs))?;
+
+        let prices = Arc::new(Prices::default());
+        let clock = client.config.clock.clone();
+        let task = tokio::spawn({
+            let prices = prices.clone();
+            async move {
+                while let Some(msg) = ws_client.recv().await {
+                    let Some(timestamp) = msg.timestamp() else {
+                        continue;
+                    };
+                    let received_at = clock.0.now();
+                    for (pair, price) in msg.prices() {
+                        let price = BookPrice {
+                            pair: pair.clone(),
+                            price,
+                            timestamp,
+                            received_at,
+                            origin: PriceOrigin::WebSocket,
+                        };
+                        publish(&prices, pair, price);
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            client: client.clone(),
+            prices,
+            max_age: DEFAULT_PRICE_BOOK_MAX_AGE,
+            task,
+        })
+    }
+
+    /// Sets the age after which a WebSocket price is stale, `DEFAULT_PRICE_BOOK_MAX_AGE` by default.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Returns the latest WebSocket price of `pair`, fresh or not.
+    pub fn latest(&self, pair: &str) -> Option<BookPrice> {
+        let prices = self.prices.read().unwrap_or_else(|e| e.into_inner());
+        let price = prices.get(pair)?.borrow().clone();
+        price
+    }
+
+    /// Returns the latest WebSocket price of `pair` if it is not older than the `max_age` of the
+    /// book. Otherwise fetches it with `get_entry` for the pairs like "BTC/USD", or fails with
+    /// `StalePrice` or `PriceUnavailable` for the others.
+    pub async fn get(&self, pair: &str) -> Result<BookPrice, PragmaError> {
+        let latest = self.latest(pair);
+        let freshness = match &latest {
+            Some(price) => self
+                .client
+                .config
+                .clock
+                .check_freshness(price.timestamp, Some(self.max_age)),
+            None => Err(PragmaError::PriceUnavailable(pair.to_string())),
+        };
+        if let (Some(price), Ok(())) = (&latest, &freshness) {
+            return Ok(price.clone());
+        }
+
+        let Some((base, quote)) = pair.split_once('/') else {
+            return Err(freshness.unwrap_err());
+        };
+        let entry = self.client.get_entry(base, quote, None).await?;
+        Ok(BookPrice {
+            pair: pair.to_string(),
+            price: entry.price,
+            timestamp: entry.timestamp,
+            received_at: self.client.config.clock.0.now(),
+            origin: PriceOrigin::Http,
+        })
+    }
+
+    /// Returns a receiver of the WebSocket prices of `pair`, holding `None` until the first one.
+    pub fn watch(&self, pair: &str) -> watch::Receiver<Option<BookPrice>> {
+        let mut prices = self.prices.write().unwrap_or_else(|e| e.into_inner());
+        prices
+            .entry(pair.to_string())
+            .or_insert_with(|| watch::channel(None).0)
+            .subscribe()
+    }
+
+    /// Returns the pairs with a WebSocket price.
+    pub fn pairs(&self) -> Vec<String> {
+        let prices = self.prices.read().unwrap_or_else(|e| e.into_inner());
+        prices
+            .iter()
+            .filter(|(_, sender)| sender.borrow().is_some())
+            .map(|(pair, _)| pair.clone())
+            .collect()
+    }
+}
+
+fn publish(prices: &Prices, pair: String, price: BookPrice) {
+    {
+        let prices = prices.read().unwrap_or_else(|e| e.into_inner());
+        if let Some(sender) = prices.get(&pair) {
+            sender.send_replace(Some(price));
+            return;
+        }
+    }
+    let mut prices = prices.write().unwrap_or_else(|e| e.into_inner());
+    prices
+        .entry(pair)
+        .or_insert_with(|| watch::channel(None).0)
+        .send_replace(Some(price));
+}
@@ -0,0 +1,340 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
+
+use crate::{AggregationMethod, Aggregator, LightspeedMessage, PragmaError, Sample, Timestamp};
+
+use super::{PragmaWsClient, WsError};
+
+/// Configuration of the rolling windows of a [`RollingWindows`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RollingConfig {
+    /// The length of the windows used for the TWAP, min/max and volatility.
+    pub window: Duration,
+
+    /// The time after which the weight of a price in the EMA is halved.
+    pub ema_half_life: Duration,
+
+    /// The longest expected time between two updates of a pair. After a longer gap, the window
+    /// and the EMA of the pair restart from the next update, and its stats are flagged as stale
+    /// until then.
+    pub max_gap: Duration,
+}
+
+impl Default for RollingConfig {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(60),
+            ema_half_life: Duration::from_secs(10),
+            max_gap: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Statistics of the rolling window of a pair. Prices are in the units of the API.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RollingStats {
+    /// The latest price.
+    pub last_price: f64,
+
+    /// The timestamp of the latest price.
+    pub last_update: Timestamp,
+
+    /// The number of prices in the window. Once no price was received for longer than the window,
+    /// it is `0` and the TWAP, min and max are the latest price, which is still in effect.
+    pub samples: usize,
+
+    /// The time-weighted average price over the window.
+    pub twap: f64,
+
+    /// The exponential moving average of the prices.
+    pub ema: f64,
+
+    /// The lowest price of the window.
+    pub min: f64,
+
+    /// The highest price of the window.
+    pub max: f64,
+
+    /// The realized volatility over the window: the square root of the sum of the squared log
+    /// returns between consecutive prices. `None` with less than two prices.
+    pub volatility: Option<f64>,
+
+    /// Whether the latest price is older than `max_gap`, i.e. the updates stopped.
+    pub stale: bool,
+
+    /// The number of gaps longer than `max_gap` seen so far for the pair.
+    pub gaps: u64,
+}
+
+#[derive(Debug, Default)]
+struct PairWindow {
+    samples: VecDeque<(Timestamp, f64)>,
+    ema: Option<(Timestamp, f64)>,
+    gaps: u64,
+}
+
+impl PairWindow {
+    fn push(&mut self, config: &RollingConfig, timestamp: Timestamp, price: f64) {
+        if let Some(&(last, _)) = self.samples.back() {
+            if timestamp <= last {
+                // Updates are expected in order: drop duplicates and late ones.
+                return;
+            }
+            if timestamp.saturating_duration_since(last) > config.max_gap {
+                self.gaps += 1;
+                self.samples.clear();
+                self.ema = None;
+            }
+        }
+
+        self.ema = Some(match self.ema {
+            Some((at, ema)) => {
+                let elapsed = timestamp.saturating_duration_since(at).as_secs_f64();
+                let half_life = config.ema_half_life.as_secs_f64();
+                let alpha = if half_life > 0.0 {
+                    1.0 - (-elapsed * std::f64::consts::LN_2 / half_life).exp()
+                } else {
+                    1.0
+                };
+                (timestamp, ema + alpha * (price - ema))
+            }
+            None => (timestamp, price),
+        });
+
+        self.samples.push_back((timestamp, price));
+        let start = timestamp - config.window;
+        while self.samples.front().is_some_and(|(at, _)| *at < start) {
+            self.samples.pop_front();
+        }
+    }
+
+    fn stats(&self, config: &RollingConfig, now: Timestamp) -> Option<RollingStats> {
+        let &(last_update, last_price) = self.samples.back()?;
+        let (_, ema) = self.ema?;
+
+        // The samples are only dropped when a newer one is pushed, so the oldest ones may have
+        // left the window since.
+        let start = now - config.window;
+        let window: Vec<(Timestamp, f64)> = self
+            .samples
+            .iter()
+            .skip_while(|(at, _)| *at < start)
+            .copied()
+            .collect();
+
+        let samples: Vec<Sample> = window
+            .iter()
+            .map(|(at, price)| Sample::new("", *price, *at))
+            .collect();
+        let twap = Aggregator::new(AggregationMethod::Twap)
+            .twap_until(now.max(last_update))
+            .aggregate(&samples)
+            .ok()
+            .flatten()
+            .unwrap_or(last_price);
+
+        let (min, max) = if window.is_empty() {
+            (last_price, last_price)
+        } else {
+            let prices = window.iter().map(|(_, price)| *price);
+            (
+                prices.clone().fold(f64::INFINITY, f64::min),
+                prices.fold(f64::NEG_INFINITY, f64::max),
+            )
+        };
+
+        let volatility = (window.len() > 1).then(|| {
+            window
+                .iter()
+                .zip(window.iter().skip(1))
+                .filter(|((_, previous), (_, price))| *previous > 0.0 && *price > 0.0)
+                .map(|((_, previous), (_, price))| (price / previous).ln().powi(2))
+                .sum::<f64>()
+                .sqrt()
+        });
+
+        Some(RollingStats {
+            last_price,
+            last_update,
+            samples: window.len(),
+            twap,
+            ema,
+            min,
+            max,
+            volatility,
+            stale: now.saturating_duration_since(last_update) > config.max_gap,
+            gaps: self.gaps,
+        })
+    }
+}
+
+/// Rolling windows of the prices of every pair of a Lightspeed stream.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use pragma_rs::{RollingConfig, RollingWindows, Timestamp};
+///
+/// let mut windows = RollingWindows::new(RollingConfig::default());
+/// let start = Timestamp::from_secs(1_700_000_000);
+/// windows.push("BTC/USD", 100.0, start);
+/// windows.push("BTC/USD", 110.0, start + Duration::from_secs(1));
+///
+/// let stats = windows.stats("BTC/USD", start + Duration::from_secs(2)).unwrap();
+/// assert_eq!(stats.twap, 105.0);
+/// assert_eq!((stats.min, stats.max), (100.0, 110.0));
+/// assert!(!stats.stale);
+///
+/// // A minute later, both prices left the window of 60 seconds.
+/// let stats = windows.stats("BTC/USD", start + Duration::from_secs(62)).unwrap();
+/// assert_eq!(stats.samples, 0);
+/// assert_eq!((stats.twap, stats.min, stats.max), (110.0, 110.0, 110.0));
+/// assert!(stats.stale);
+/// ```
+#[derive(Debug, Default)]
+pub struct RollingWindows {
+    config: RollingConfig,
+    pairs: HashMap<String, PairWindow>,
+}
+
+impl RollingWindows {
+    pub fn new(config: RollingConfig) -> Self {
+        Self {
+            config,
+            pairs: HashMap::new(),
+        }
+    }
+
+    /// Adds the price of `pair` published at `timestamp`.
+    pub fn push(&mut self, pair: &str, price: f64, timestamp: Timestamp) {
+        self.pairs
+            .entry(pair.to_string())
+            .or_default()
+            .push(&self.config, timestamp, price);
+    }
+
+    /// Adds the prices of a `LightspeedMessage::PriceUpdate`, and ignores the other messages.
+    ///
+    /// The prices that cannot be parsed are skipped, the other ones are still added: the error of
+    /// the first one is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pragma_rs::{LightspeedMessage, PriceUpdate, RollingConfig, RollingWindows, Timestamp};
+    ///
+    /// let update = |pair: &str, price: &str| PriceUpdate {
+    ///     num_sources_aggregated: 3,
+    ///     pair_id: pair.to_string(),
+    ///     price: price.to_string(),
+    /// };
+    /// let msg = LightspeedMessage::PriceUpdate {
+    ///     oracle_prices: vec![update("ETH/USD", "zz"), update("BTC/USD", "0x64")],
+    ///     timestamp: Timestamp::from_secs(1_700_000_000),
+    /// };
+    ///
+    /// let mut windows = RollingWindows::new(RollingConfig::default());
+    /// assert!(windows.update(&msg).is_err());
+    /// assert_eq!(windows.pairs().collect::<Vec<_>>(), ["BTC/USD"]);
+    /// ```
+    pub fn update(&mut self, msg: &LightspeedMessage) -> Result<(), PragmaError> {
+        let mut result = Ok(());
+        if let LightspeedMessage::PriceUpdate {
+            oracle_prices,
+            timestamp,
+        } = msg
+        {
+            for update in oracle_prices {
+                match update.to_sample(*timestamp) {
+                    Ok(sample) => self.push(&update.pair_id, sample.price, *timestamp),
+                    Err(e) => {
+                        if result.is_ok() {
+                            result = Err(e);
+                        }
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// Returns the statistics of `pair` at `now`, or `None` if no price was received for it.
+    pub fn stats(&self, pair: &str, now: Timestamp) -> Option<RollingStats> {
+        self.pairs.get(pair)?.stats(&self.config, now)
+    }
+
+    /// Returns the pairs with at least one price.
+    pub fn pairs(&self) -> impl Iterator<Item = &str> {
+        self.pairs.keys().map(String::as_str)
+    }
+}
+
+/// A Lightspeed client maintaining the rolling windows of the pairs it receives, see
+/// [`PragmaWsClient::rolling`].
+pub struct RollingStream {
+    client: PragmaWsClient<LightspeedMessage>,
+    windows: RollingWindows,
+}
+
+impl RollingStream {
+    /// Sends a message to the WebSocket, e.g. a subscription.
+    pub fn send(&self, msg: LightspeedMessage) -> Result<(), WsError> {
+        self.client.send(msg)
+    }
+
+    /// Receives the next message, after adding its prices to the windows. Prices that cannot be
+    /// parsed are skipped.
+    pub async fn recv(&mut self) -> Option<LightspeedMessage> {
+        let msg = self.client.recv().await?;
+        let _ = self.windows.update(&msg);
+        Some(msg)
+    }
+
+    /// Returns the statistics of `pair` now, according to the clock of the client.
+    pub fn stats(&self, pair: &str) -> Option<RollingStats> {
+        self.windows.stats(pair, self.client.clock.0.now())
+    }
+
+    pub fn windows(&self) -> &RollingWindows {
+        &self.windows
+    }
+}
+
+impl PragmaWsClient<LightspeedMessage> {
+    /// Turns a Lightspeed client into a stream maintaining rolling windows of the received prices.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pragma_rs::{Config, Environment, LightspeedMessage, PragmaClient, RollingConfig};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let config = Config::new("your_api_key".to_string(), Environment::Development);
+    ///     let client = PragmaClient::new(config)?;
+    ///     let mut ws_client = client.lightspeed_ws_client();
+    ///     ws_client.connect().await?;
+    ///
+    ///     let mut stream = ws_client.rolling(RollingConfig::default());
+    ///     stream.send(LightspeedMessage::Subscribe {
+    ///         msg_type: "subscribe".to_string(),
+    ///         pairs: vec!["BTC/USD".to_string()],
+    ///     })?;
+    ///     while stream.recv().await.is_some() {
+    ///         if let Some(stats) = stream.stats("BTC/USD") {
+    ///             println!("TWAP: {}, EMA: {}", stats.twap, stats.ema);
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn rolling(self, config: RollingConfig) -> RollingStream {
+        RollingStream {
+            client: self,
+            windows: RollingWindows::new(config),
+        }
+    }
+}
@@ -1,10 +1,12 @@
-use crate::PragmaClient;
+use std::collections::HashSet;
+
+use crate::{telemetry, PragmaClient, Timestamp};
 use serde::{Deserialize, Serialize};
 
-use super::PragmaWsClient;
+use super::{PairMessage, PragmaWsClient, Timestamped};
 
 /// Enum representing the possible messages for the Starkex WebSocket endpoint.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum StarkexMessage {
     Subscribe {
@@ -19,12 +21,13 @@ pub enum StarkexMessage {
     },
     PriceUpdate {
         oracle_prices: Vec<PriceUpdate>,
-        timestamp: i64,
+        #[serde(with = "crate::timestamp::seconds")]
+        timestamp: Timestamp,
     },
 }
 
 /// Struct representing a price update from the Starkex endpoint.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PriceUpdate {
     /// The global identifier for the asset.
     pub global_asset_id: String,
@@ -40,7 +43,7 @@ pub struct PriceUpdate {
 }
 
 /// Struct representing a signed price from an individual oracle.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SignedPrice {
     /// The identifier for the oracle's asset.
     pub oracle_asset_id: String,
@@ -51,25 +54,146 @@ pub struct SignedPrice {
     /// The public key used for signing the price.
     pub signing_key: String,
 
-    /// The timestamp of the price data.
-    pub timestamp: i64,
+    /// The timestamp of the price data, encoded in seconds.
+    #[serde(with = "crate::timestamp::seconds")]
+    pub timestamp: Timestamp,
 
     /// The cryptographic signature of the price data.
     pub signature: String,
 }
 
+impl Timestamped for StarkexMessage {
+    fn timestamp(&self) -> Option<Timestamp> {
+        match self {
+            Self::PriceUpdate { timestamp, .. } => Some(*timestamp),
+            _ => None,
+        }
+    }
+}
+
+impl PriceUpdate {
+    /// Returns the pair of the update (e.g. "BTC/USD"), decoded from its global asset id, which
+    /// encodes the pair and its decimals (e.g. "BTC-USD-8") as a hexadecimal string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pragma_rs::StarkexPriceUpdate;
+    ///
+    /// let update = StarkexPriceUpdate {
+    ///     global_asset_id: "0x4254432d5553442d38000000000000".to_string(),
+    ///     median_price: "0x64".to_string(),
+    ///     signature: "0x0".to_string(),
+    ///     signed_prices: vec![],
+    /// };
+    /// assert_eq!(update.pair().as_deref(), Some("BTC/USD"));
+    /// ```
+    pub fn pair(&self) -> Option<String> {
+        let hex = self.global_asset_id.trim_start_matches("0x");
+        let bytes: Option<Vec<u8>> = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+            .collect();
+        let asset = String::from_utf8(bytes?).ok()?;
+        let mut parts = asset.trim_matches('\0').split('-');
+        let (base, quote) = (parts.next()?, parts.next()?);
+        (!base.is_empty() && !quote.is_empty()).then(|| format!("{base}/{quote}"))
+    }
+}
+
+impl PairMessage for StarkexMessage {
+    fn subscribe(pairs: Vec<String>) -> Self {
+        Self::Subscribe {
+            msg_type: "subscribe".to_string(),
+            pairs,
+        }
+    }
+
+    fn unsubscribe(pairs: Vec<String>) -> Self {
+        Self::Unsubscribe {
+            msg_type: "unsubscribe".to_string(),
+            pairs,
+        }
+    }
+
+    fn subscribed_pairs(&self) -> Option<&[String]> {
+        match self {
+            Self::Subscribe { pairs, .. } => Some(pairs),
+            _ => None,
+        }
+    }
+
+    fn unsubscribed_pairs(&self) -> Option<&[String]> {
+        match self {
+            Self::Unsubscribe { pairs, .. } => Some(pairs),
+            _ => None,
+        }
+    }
+
+    /// Returns the prices by pair, or by global asset id for the ids not decoding to a pair.
+    fn prices(&self) -> Vec<(String, String)> {
+        match self {
+            Self::PriceUpdate { oracle_prices, .. } => oracle_prices
+                .iter()
+                .map(|u| {
+                    let pair = u.pair().unwrap_or_else(|| u.global_asset_id.clone());
+                    (pair, u.median_price.clone())
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Keeps the updates whose global asset id is in `pairs` or decodes to one of `pairs`.
+    fn retain(&self, pairs: &HashSet<String>) -> Option<Self> {
+        let Self::PriceUpdate {
+            oracle_prices,
+            timestamp,
+        } = self
+        else {
+            return None;
+        };
+        let oracle_prices: Vec<PriceUpdate> = oracle_prices
+            .iter()
+            .filter(|u| {
+                pairs.contains(&u.global_asset_id) || u.pair().is_some_and(|p| pairs.contains(&p))
+            })
+            .cloned()
+            .collect();
+        (!oracle_prices.is_empty()).then_some(Self::PriceUpdate {
+            oracle_prices,
+            timestamp: *timestamp,
+        })
+    }
+}
+
+/// The path of the endpoint, appended to the WebSocket URL of the environment.
+const PATH: &str = "/node/v1/data/subscribe";
+
 impl PragmaClient {
     /// Creates a WebSocket client for the Starkex endpoint.
     ///
     /// This method configures a `PragmaWsClient` to connect to the Starkex WebSocket endpoint,
     /// which provides verifiable price updates with cryptographic signatures.
     pub fn starkex_ws_client(&self) -> PragmaWsClient<StarkexMessage> {
-        let url = format!("{}/node/v1/data/subscribe", self.config.ws_url);
-        let api_key = self.config.api_key.clone();
-        PragmaWsClient::new(url, api_key, |msg| {
-            serde_json::from_str::<String>(&msg).map_or(None, |msg| {
+        let url = format!("{}{PATH}", self.config.ws_url);
+        PragmaWsClient::with_api_key(url, self.config.api_key.clone(), |msg| {
+            let msg = serde_json::from_str::<String>(&msg).map_or(None, |msg| {
                 serde_json::from_str::<StarkexMessage>(&msg).ok()
-            })
+            })?;
+            if let StarkexMessage::PriceUpdate {
+                oracle_prices,
+                timestamp,
+            } = &msg
+            {
+                for update in oracle_prices {
+                    telemetry::ws_price_update("starkex", &update.global_asset_id, *timestamp);
+                }
+            }
+            Some(msg)
         })
+        .with_cassette(self.recorder.clone(), self.replayer.clone())
+        .with_clock(self.config.clock.clone())
+        .with_endpoints(self, PATH)
     }
 }
@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use pragma_rs::{
+    Candle, CandleAggregator, Interval, LateTickPolicy, LightspeedMessage, PriceUpdate, Timestamp,
+};
+
+fn update(pair: &str, price: &str) -> PriceUpdate {
+    PriceUpdate {
+        num_sources_aggregated: 5,
+        pair_id: pair.to_string(),
+        price: price.to_string(),
+    }
+}
+
+fn message(millis: u64, updates: Vec<PriceUpdate>) -> LightspeedMessage {
+    LightspeedMessage::PriceUpdate {
+        oracle_prices: updates,
+        timestamp: Timestamp::from_millis(millis),
+    }
+}
+
+fn starts(candles: &[Candle]) -> Vec<(&str, u64)> {
+    candles
+        .iter()
+        .map(|c| (c.pair.as_str(), c.start.as_millis() / 1000))
+        .collect()
+}
+
+#[test]
+fn drop_policy_closes_on_the_next_interval_and_drops_late_ticks() {
+    let mut candles = CandleAggregator::new(Interval::OneSecond, LateTickPolicy::Drop);
+    candles
+        .push_update(&update("BTC/USD", "0x64"), Timestamp::from_millis(10_200))
+        .unwrap();
+    // Out of order within the open candle: becomes its open price.
+    candles
+        .push_update(&update("BTC/USD", "0x5a"), Timestamp::from_millis(10_100))
+        .unwrap();
+
+    let closed = candles
+        .push_update(&update("BTC/USD", "0x6e"), Timestamp::from_millis(11_000))
+        .unwrap();
+    assert_eq!(starts(&closed), [("BTC/USD", 10)]);
+    assert_eq!((closed[0].open, closed[0].close), (90.0, 100.0));
+    assert_eq!(closed[0].ticks, 2);
+
+    let late = candles
+        .push_update(&update("BTC/USD", "0x78"), Timestamp::from_millis(10_900))
+        .unwrap();
+    assert!(late.is_empty());
+    assert_eq!(candles.late_ticks(), 1);
+    assert_eq!(candles.open_candles("BTC/USD")[0].ticks, 1);
+}
+
+#[test]
+fn grace_policy_applies_delayed_ticks() {
+    let policy = LateTickPolicy::Grace(Duration::from_millis(500));
+    let mut candles = CandleAggregator::new(Interval::OneSecond, policy);
+    candles
+        .push_update(&update("BTC/USD", "0x64"), Timestamp::from_millis(10_500))
+        .unwrap();
+    assert!(candles
+        .push_update(&update("BTC/USD", "0x6e"), Timestamp::from_millis(11_200))
+        .unwrap()
+        .is_empty());
+
+    // Delayed by 700ms, within the grace period of the candle ending at 11s.
+    candles
+        .push_update(&update("BTC/USD", "0xc8"), Timestamp::from_millis(10_600))
+        .unwrap();
+    let closed = candles
+        .push_update(&update("BTC/USD", "0x6e"), Timestamp::from_millis(11_500))
+        .unwrap();
+    assert_eq!(starts(&closed), [("BTC/USD", 10)]);
+    assert_eq!((closed[0].high, closed[0].ticks), (200.0, 2));
+
+    candles
+        .push_update(&update("BTC/USD", "0x64"), Timestamp::from_millis(10_700))
+        .unwrap();
+    assert_eq!(candles.late_ticks(), 1);
+}
+
+#[test]
+fn close_until_closes_the_candles_of_silent_pairs() {
+    let policy = LateTickPolicy::Grace(Duration::from_secs(1));
+    let mut candles = CandleAggregator::new(Interval::OneSecond, policy);
+    let msg = message(
+        10_500,
+        vec![update("BTC/USD", "0x64"), update("ETH/USD", "0xa")],
+    );
+    candles.push(&msg).unwrap();
+
+    // The candles end at 11s and stay open for the grace period.
+    assert!(candles
+        .close_until(Timestamp::from_millis(11_900))
+        .is_empty());
+    let mut closed = candles.close_until(Timestamp::from_secs(12));
+    closed.sort_by(|a, b| a.pair.cmp(&b.pair));
+    assert_eq!(starts(&closed), [("BTC/USD", 10), ("ETH/USD", 10)]);
+    assert!(candles.open_candles("BTC/USD").is_empty());
+}
+
+#[test]
+fn invalid_price_leaves_the_candles_untouched() {
+    let mut candles = CandleAggregator::new(Interval::OneMinute, LateTickPolicy::Drop);
+    candles
+        .push(&message(60_000, vec![update("BTC/USD", "0x64")]))
+        .unwrap();
+
+    let invalid = message(
+        120_000,
+        vec![update("BTC/USD", "0x6e"), update("ETH/USD", "zz")],
+    );
+    assert!(candles.push(&invalid).is_err());
+    assert_eq!(candles.open_candles("BTC/USD").len(), 1);
+    assert!(candles.open_candles("ETH/USD").is_empty());
+
+    // The candle is still closed by the next valid message.
+    let closed = candles
+        .push(&message(120_000, vec![update("BTC/USD", "0x6e")]))
+        .unwrap();
+    assert_eq!(starts(&closed), [("BTC/USD", 60)]);
+}
@@ -0,0 +1,160 @@
+use pragma_rs::{Config, Environment, PragmaError};
+
+/// Returns the HTTP and WebSocket URLs of `config`.
+fn urls(config: &Config) -> (String, String) {
+    (config.base_url().to_string(), config.ws_url().to_string())
+}
+
+fn expected(http_base_url: &str, ws_base_url: &str) -> (String, String) {
+    (http_base_url.to_string(), ws_base_url.to_string())
+}
+
+#[test]
+fn environment_from_str() {
+    assert!(matches!("devnet".parse(), Ok(Environment::Development)));
+    assert!(matches!(" PROD ".parse(), Ok(Environment::Production)));
+    assert!(matches!(
+        "staging".parse::<Environment>(),
+        Err(PragmaError::InvalidConfig(_))
+    ));
+    assert!("wss://pragma.internal".parse::<Environment>().is_err());
+    assert!("https://".parse::<Environment>().is_err());
+
+    let cases = [
+        (
+            "https://pragma.internal/",
+            "https://pragma.internal",
+            "wss://pragma.internal",
+        ),
+        (
+            "HTTPS://Pragma.Internal",
+            "https://pragma.internal",
+            "wss://pragma.internal",
+        ),
+        (
+            "http://localhost:3000/api/",
+            "http://localhost:3000/api",
+            "ws://localhost:3000/api",
+        ),
+    ];
+    for (url, http, ws) in cases {
+        let Ok(Environment::Local {
+            http_base_url,
+            ws_base_url,
+        }) = url.parse()
+        else {
+            panic!("{url} is not a local environment");
+        };
+        assert_eq!((http_base_url, ws_base_url), expected(http, ws), "{url}");
+    }
+}
+
+/// The only test reading the environment variables, so that the others do not race with it.
+#[test]
+fn config_from_env() {
+    let vars = [
+        "PRAGMA_API_KEY",
+        "PRAGMA_ENVIRONMENT",
+        "PRAGMA_HTTP_URL",
+        "PRAGMA_WS_URL",
+    ];
+    let set = |values: [&str; 4]| {
+        for (name, value) in vars.iter().zip(values) {
+            std::env::set_var(name, value);
+        }
+    };
+
+    set(["", "prod", "", ""]);
+    assert!(matches!(
+        Config::from_env(),
+        Err(PragmaError::InvalidConfig(_))
+    ));
+
+    set(["your_api_key", "", "", ""]);
+    let config = Config::from_env().unwrap();
+    assert_eq!(
+        urls(&config),
+        expected(
+            "https://api.devnet.pragma.build",
+            "wss://api.devnet.pragma.build"
+        )
+    );
+
+    set(["your_api_key", "prod", "HTTPS://Pragma.Internal/", ""]);
+    let config = Config::from_env().unwrap();
+    assert_eq!(
+        urls(&config),
+        expected("https://pragma.internal", "wss://pragma.internal")
+    );
+
+    set([
+        "your_api_key",
+        "prod",
+        "http://localhost:3000",
+        "WSS://stream.internal/",
+    ]);
+    let config = Config::from_env().unwrap();
+    assert_eq!(
+        urls(&config),
+        expected("http://localhost:3000", "wss://stream.internal")
+    );
+
+    set(["your_api_key", "prod", "", "https://pragma.internal"]);
+    assert!(Config::from_env().is_err());
+
+    set(["your_api_key", "mainnet", "", ""]);
+    assert!(Config::from_env().is_err());
+
+    for name in vars {
+        std::env::remove_var(name);
+    }
+}
+
+#[cfg(feature = "config-file")]
+#[test]
+fn config_from_file() {
+    let dir = std::env::temp_dir().join(format!("pragma-rs-config-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = |name: &str, content: &str| {
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    };
+
+    let toml = file(
+        "pragma.toml",
+        "api_key = \"your_api_key\"\nenvironment = \"HTTPS://pragma.internal\"\n",
+    );
+    let config = Config::from_file(toml).unwrap();
+    assert_eq!(
+        urls(&config),
+        expected("https://pragma.internal", "wss://pragma.internal")
+    );
+
+    let json = file(
+        "pragma.json",
+        r#"{"api_key": "your_api_key", "environment": "prod", "ws_url": "wss://stream.internal"}"#,
+    );
+    let config = Config::from_file(json).unwrap();
+    assert_eq!(
+        urls(&config),
+        expected(
+            "https://api.production.pragma.build",
+            "wss://stream.internal"
+        )
+    );
+
+    let unknown = file("unknown.toml", "api_key = \"your_api_key\"\nretries = 3\n");
+    assert!(matches!(
+        Config::from_file(unknown),
+        Err(PragmaError::InvalidConfig(_))
+    ));
+    let yaml = file("pragma.yaml", "api_key: your_api_key\n");
+    assert!(matches!(
+        Config::from_file(yaml),
+        Err(PragmaError::InvalidConfig(_))
+    ));
+    assert!(Config::from_file(dir.join("missing.toml")).is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
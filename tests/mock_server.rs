@@ -0,0 +1,1227 @@
+use std::time::Duration;
+
+use futures_util::StreamExt;
+
+use pragma_rs::{
+    mock_server::{Fault, MockServer},
+    Config, DeviationEvent, DeviationMonitor, EntryCacheConfig, Environment, FailoverConfig,
+    FundingRateInstrument, FundingRatesEntry, FundingSource, GetEntryParams, GetEntryResponse,
+    GetOnchainEntryResponse, HistoryChunking, LightspeedMessage, ManualClock, PairMessage,
+    PragmaClient, PragmaError, PragmaWsClient, PriceBook, PriceOrigin, PriceUpdate, RateLimit,
+    RateLimitConfig, ReconnectConfig, ReplayTiming, RollingConfig, RotatingApiKey, StarkexMessage,
+    StarkexPriceUpdate, StarknetNetwork, Timestamp, WsError, DEFAULT_HUB_CAPACITY,
+};
+
+const API_KEY: &str = "test_api_key";
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+fn entry(pair: &str, price: u128, timestamp: Timestamp) -> GetEntryResponse {
+    GetEntryResponse {
+        decimals: 8,
+        num_sources_aggregated: 5,
+        pair_id: pair.to_string(),
+        price: format!("{price:#x}"),
+        timestamp,
+        components: None,
+    }
+}
+
+fn funding_rate(source: &str, hourly_rate: f64, timestamp: Timestamp) -> FundingRatesEntry {
+    FundingRatesEntry {
+        hourly_rate,
+        pair: "BTC/USD".to_string(),
+        source: source.to_string(),
+        timestamp,
+    }
+}
+
+async fn setup() -> (MockServer, PragmaClient) {
+    let server = MockServer::start().await.unwrap();
+    server.require_api_key(API_KEY);
+    let client = PragmaClient::new(server.config(API_KEY)).unwrap();
+    (server, client)
+}
+
+#[tokio::test]
+async fn get_entry_from_fixture() {
+    let (server, client) = setup().await;
+    server.set_entry(
+        "BTC",
+        "USD",
+        entry("BTC/USD", 100_000_000, Timestamp::from_secs(1)),
+    );
+
+    let response = client.get_entry("BTC", "USD", None).await.unwrap();
+    assert_eq!(response.price_u128().unwrap(), 100_000_000);
+    assert_eq!(response.timestamp, Timestamp::from_secs(1));
+
+    let requests = server.requests();
+    assert_eq!(requests[0].path, "/node/v1/data/BTC/USD");
+    assert_eq!(requests[0].api_key.as_deref(), Some(API_KEY));
+
+    let missing = client.get_entry("ETH", "USD", None).await;
+    assert!(matches!(missing, Err(PragmaError::ApiError(_))));
+}
+
+#[tokio::test]
+async fn invalid_api_key_is_unauthorized() {
+    let (server, _) = setup().await;
+    let client = PragmaClient::new(server.config("wrong_key")).unwrap();
+
+    let response = client.get_entry("BTC", "USD", None).await;
+    assert!(matches!(response, Err(PragmaError::Unauthorized(_))));
+}
+
+#[tokio::test]
+async fn injected_faults() {
+    let (server, client) = setup().await;
+    server.set_entry("BTC", "USD", entry("BTC/USD", 1, Timestamp::from_secs(1)));
+    assert!(client.is_healthy().await);
+
+    server.inject_fault(Fault::Status(503));
+    assert!(!client.is_healthy().await);
+    let response = client.get_entry("BTC", "USD", None).await;
+    assert!(matches!(response, Err(PragmaError::ApiError(_))));
+
+    server.clear_faults();
+    server.inject_fault(Fault::MalformedJson);
+    let response = client.get_entry("BTC", "USD", None).await;
+    assert!(matches!(response, Err(PragmaError::JsonError(_))));
+
+    server.clear_faults();
+    server.inject_fault(Fault::Latency(Duration::from_secs(3)));
+    assert!(!client.is_healthy().await);
+}
+
+#[tokio::test]
+async fn historical_funding_rates_are_chunked() {
+    let (server, client) = setup().await;
+    let start = Timestamp::from_secs(1_746_000_000);
+    let entries = (0..72)
+        .map(|hour| {
+            funding_rate(
+                "hyperliquid",
+                0.01,
+                start + Duration::from_secs(hour * 3600),
+            )
+        })
+        .collect();
+    server.set_historical_funding_rates("BTC", "USD", "hyperliquid", entries);
+
+    let chunking = HistoryChunking {
+        chunk: DAY,
+        max_concurrency: 2,
+    };
+    let response = client
+        .get_historical_funding_rates_chunked(
+            "BTC",
+            "USD",
+            start,
+            start + DAY * 3,
+            &FundingSource::Hyperliquid,
+            chunking,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.len(), 72);
+    assert!(response.windows(2).all(|w| w[0].timestamp < w[1].timestamp));
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn historical_funding_rates_of_a_reversed_range_are_empty() {
+    let (server, client) = setup().await;
+    let start = Timestamp::from_secs(1_746_000_000);
+    let response = client
+        .get_historical_funding_rates(
+            "BTC",
+            "USD",
+            start + DAY,
+            start,
+            &FundingSource::Hyperliquid,
+        )
+        .await
+        .unwrap();
+
+    assert!(response.is_empty());
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test]
+async fn historical_funding_rates_windows_are_lazy() {
+    let (server, client) = setup().await;
+    let start = Timestamp::from_secs(1_746_000_000);
+    server.set_historical_funding_rates(
+        "BTC",
+        "USD",
+        "hyperliquid",
+        vec![funding_rate("hyperliquid", 0.01, start)],
+    );
+    let chunking = HistoryChunking {
+        chunk: Duration::from_millis(1),
+        max_concurrency: 2,
+    };
+    let source = FundingSource::Hyperliquid;
+
+    // About 3·10^10 windows: building them upfront would exhaust memory.
+    let mut pages = Box::pin(client.stream_historical_funding_rates(
+        "BTC",
+        "USD",
+        start,
+        start + DAY * 365,
+        &source,
+        chunking,
+    ));
+    let first = tokio::time::timeout(Duration::from_secs(5), pages.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(first.is_ok());
+}
+
+#[tokio::test]
+async fn funding_rates_across_sources() {
+    let (server, client) = setup().await;
+    let now = Timestamp::from_secs(1_746_000_000);
+    server.set_funding_rate_instruments(
+        [
+            FundingSource::Hyperliquid,
+            FundingSource::Paradex,
+            FundingSource::Kraken,
+        ]
+        .into_iter()
+        .map(|source| FundingRateInstrument {
+            source,
+            pair: "BTC/USD".to_string(),
+            first_timestamp: now,
+            last_timestamp: now,
+        })
+        .collect(),
+    );
+    server.set_funding_rates("BTC", "USD", funding_rate("hyperliquid", 0.01, now));
+    server.set_funding_rates("BTC", "USD", funding_rate("paradex", 0.03, now));
+
+    let sources = client.list_funding_rate_sources().await.unwrap();
+    assert_eq!(
+        sources,
+        [
+            FundingSource::Hyperliquid,
+            FundingSource::Paradex,
+            FundingSource::Kraken,
+        ]
+    );
+
+    let response = client
+        .get_funding_rates_all_sources("BTC", "USD", None)
+        .await
+        .unwrap();
+    let stats = response.stats.unwrap();
+    assert_eq!(response.rates.len(), 2);
+    assert_eq!(stats.max.0, FundingSource::Paradex);
+    // Kraken lists the pair but has no current rate.
+    assert_eq!(
+        response.errors.keys().collect::<Vec<_>>(),
+        [&FundingSource::Kraken]
+    );
+    assert!((stats.spread - 0.02).abs() < 1e-12);
+}
+
+#[tokio::test]
+async fn lightspeed_subscription_and_updates() {
+    let (server, client) = setup().await;
+    let mut ws_client = client.lightspeed_ws_client();
+    ws_client.connect().await.unwrap();
+
+    ws_client
+        .send(LightspeedMessage::Subscribe {
+            msg_type: "subscribe".into(),
+            pairs: vec!["BTC/USD".to_string()],
+        })
+        .unwrap();
+    let ack = tokio::time::timeout(Duration::from_secs(5), ws_client.recv()).await;
+    assert!(matches!(ack, Ok(Some(LightspeedMessage::Subscribe { .. }))));
+
+    server.push_lightspeed_raw("not json".to_string());
+    server.push_lightspeed(&LightspeedMessage::PriceUpdate {
+        oracle_prices: vec![PriceUpdate {
+            num_sources_aggregated: 5,
+            pair_id: "BTC/USD".to_string(),
+            price: "0x5f5e100".to_string(),
+        }],
+        timestamp: Timestamp::from_millis(1_746_000_000_123),
+    });
+    let update = tokio::time::timeout(Duration::from_secs(5), ws_client.recv()).await;
+    let Ok(Some(LightspeedMessage::PriceUpdate { timestamp, .. })) = update else {
+        panic!("expected a price update, got {update:?}");
+    };
+    assert_eq!(timestamp, Timestamp::from_millis(1_746_000_000_123));
+
+    assert_eq!(server.ws_connection_count(), 1);
+    server.drop_ws_connections();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(server.ws_connection_count(), 0);
+}
+
+#[tokio::test]
+async fn starkex_updates_are_string_encoded() {
+    let (server, client) = setup().await;
+    let mut ws_client = client.starkex_ws_client();
+    ws_client.connect().await.unwrap();
+
+    ws_client
+        .send(StarkexMessage::Subscribe {
+            msg_type: "subscribe".into(),
+            pairs: vec!["BTC/USD".to_string()],
+        })
+        .unwrap();
+    let ack = tokio::time::timeout(Duration::from_secs(5), ws_client.recv()).await;
+    assert!(matches!(ack, Ok(Some(StarkexMessage::Subscribe { .. }))));
+
+    server.push_starkex(&StarkexMessage::PriceUpdate {
+        oracle_prices: vec![],
+        timestamp: Timestamp::from_secs(1_746_000_000),
+    });
+    let update = tokio::time::timeout(Duration::from_secs(5), ws_client.recv()).await;
+    assert!(matches!(
+        update,
+        Ok(Some(StarkexMessage::PriceUpdate { timestamp, .. })) if timestamp == Timestamp::from_secs(1_746_000_000)
+    ));
+    assert_eq!(server.ws_messages().len(), 1);
+}
+
+#[tokio::test]
+async fn record_and_replay() {
+    let cassette =
+        std::env::temp_dir().join(format!("pragma-rs-cassette-{}.jsonl", std::process::id()));
+    let (server, _) = setup().await;
+    server.set_entry("BTC", "USD", entry("BTC/USD", 42, Timestamp::from_secs(1)));
+
+    let client = PragmaClient::new(server.config(API_KEY).record_to(&cassette)).unwrap();
+    let mut ws_client = client.lightspeed_ws_client();
+    ws_client.connect().await.unwrap();
+    ws_client
+        .send(LightspeedMessage::Subscribe {
+            msg_type: "subscribe".into(),
+            pairs: vec!["BTC/USD".to_string()],
+        })
+        .unwrap();
+    let ack = tokio::time::timeout(Duration::from_secs(5), ws_client.recv()).await;
+    assert!(matches!(ack, Ok(Some(_))));
+    client.get_entry("BTC", "USD", None).await.unwrap();
+    drop((ws_client, client, server));
+
+    // The entries are written in the background: the subscription, its ack and the request.
+    let recorded = async {
+        while pragma_rs::read_cassette(&cassette).map_or(0, |entries| entries.len()) < 3 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    };
+    tokio::time::timeout(Duration::from_secs(5), recorded)
+        .await
+        .unwrap();
+
+    let environment = Environment::Replay {
+        cassette: cassette.clone(),
+        timing: ReplayTiming::Original,
+    };
+    let client = PragmaClient::new(Config::new(API_KEY.to_string(), environment)).unwrap();
+    let response = client.get_entry("BTC", "USD", None).await.unwrap();
+    assert_eq!(response.price_u128().unwrap(), 42);
+    assert!(matches!(
+        client.get_entry("ETH", "USD", None).await,
+        Err(PragmaError::CassetteMiss(_))
+    ));
+
+    let mut ws_client = client.lightspeed_ws_client();
+    ws_client.connect().await.unwrap();
+    let replayed = tokio::time::timeout(Duration::from_secs(5), ws_client.recv()).await;
+    assert!(matches!(
+        replayed,
+        Ok(Some(LightspeedMessage::Subscribe { .. }))
+    ));
+
+    let _ = std::fs::remove_file(cassette);
+}
+
+#[tokio::test]
+async fn entry_cache_coalesces_and_expires() {
+    let (server, _) = setup().await;
+    server.set_entry("BTC", "USD", entry("BTC/USD", 1, Timestamp::from_secs(1)));
+    server.inject_fault(Fault::Latency(Duration::from_millis(100)));
+    let cache = EntryCacheConfig {
+        ttl: Duration::from_millis(300),
+        stale_while_revalidate: Duration::from_millis(300),
+    };
+    let client = PragmaClient::new(server.config(API_KEY).with_entry_cache(cache)).unwrap();
+
+    let responses =
+        futures_util::future::join_all((0..5).map(|_| client.get_entry("BTC", "USD", None))).await;
+    assert!(responses.iter().all(Result::is_ok));
+    assert_eq!(server.requests().len(), 1);
+
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    client.get_entry("BTC", "USD", None).await.unwrap();
+    let stats = client.entry_cache_stats().unwrap();
+    assert_eq!(stats.stale_hits, 1);
+    assert_eq!(stats.misses, 2);
+
+    client.invalidate_entry("btc", "usd");
+    client.get_entry("BTC", "USD", None).await.unwrap();
+    assert_eq!(client.entry_cache_stats().unwrap().misses, 3);
+}
+
+#[tokio::test]
+async fn entry_cache_drops_expired_keys() {
+    let (server, _) = setup().await;
+    server.set_entry("BTC", "USD", entry("BTC/USD", 1, Timestamp::from_secs(1)));
+    let cache = EntryCacheConfig {
+        ttl: Duration::from_millis(500),
+        stale_while_revalidate: Duration::ZERO,
+    };
+    let client = PragmaClient::new(server.config(API_KEY).with_entry_cache(cache)).unwrap();
+    let at = |secs| GetEntryParams {
+        timestamp: Some(Timestamp::from_secs(secs)),
+        ..Default::default()
+    };
+
+    for secs in 0..5 {
+        client
+            .get_entry("BTC", "USD", Some(at(secs)))
+            .await
+            .unwrap();
+    }
+    assert_eq!(client.entry_cache_stats().unwrap().entries, 5);
+
+    tokio::time::sleep(Duration::from_millis(600)).await;
+    client.get_entry("BTC", "USD", Some(at(5))).await.unwrap();
+    assert_eq!(client.entry_cache_stats().unwrap().entries, 1);
+}
+
+#[tokio::test]
+async fn rate_limiter_is_shared_across_clones() {
+    let (server, _) = setup().await;
+    server.set_entry("BTC", "USD", entry("BTC/USD", 1, Timestamp::from_secs(1)));
+    let rate_limit = RateLimitConfig::new(RateLimit {
+        requests_per_second: 10.0,
+        burst: 1,
+    });
+    let client = PragmaClient::new(server.config(API_KEY).with_rate_limit(rate_limit)).unwrap();
+    let clone = client.clone();
+
+    let started = std::time::Instant::now();
+    tokio::join!(
+        async {
+            for _ in 0..2 {
+                client.get_entry("BTC", "USD", None).await.unwrap();
+            }
+        },
+        async {
+            for _ in 0..2 {
+                clone.get_entry("BTC", "USD", None).await.unwrap();
+            }
+        },
+    );
+    assert!(started.elapsed() >= Duration::from_millis(290));
+    assert_eq!(server.requests().len(), 4);
+}
+
+#[tokio::test]
+async fn tiny_rate_limit_waits_without_overflowing() {
+    let (server, _) = setup().await;
+    server.set_entry("BTC", "USD", entry("BTC/USD", 1, Timestamp::from_secs(1)));
+    let rate_limit = RateLimitConfig::new(RateLimit {
+        requests_per_second: 1e-30,
+        burst: 1,
+    });
+    let client = PragmaClient::new(server.config(API_KEY).with_rate_limit(rate_limit)).unwrap();
+
+    client.get_entry("BTC", "USD", None).await.unwrap();
+    let throttled = tokio::time::timeout(
+        Duration::from_millis(100),
+        client.get_entry("BTC", "USD", None),
+    )
+    .await;
+    assert!(throttled.is_err());
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn non_positive_rate_limits_are_rejected() {
+    let (server, _) = setup().await;
+    let invalid = [
+        RateLimitConfig::new(RateLimit::per_second(0)),
+        RateLimitConfig::new(RateLimit::per_second(10)).endpoint(
+            "/node/v1/funding_rates",
+            RateLimit {
+                requests_per_second: f64::NAN,
+                burst: 1,
+            },
+        ),
+        RateLimitConfig::new(RateLimit {
+            requests_per_second: -1.0,
+            burst: 1,
+        }),
+    ];
+    for rate_limit in invalid {
+        let config = server.config(API_KEY).with_rate_limit(rate_limit);
+        assert!(matches!(
+            PragmaClient::new(config),
+            Err(PragmaError::InvalidConfig(_))
+        ));
+    }
+}
+
+#[tokio::test]
+async fn rate_limited_requests_are_retried_once() {
+    let (server, _) = setup().await;
+    server.set_entry("BTC", "USD", entry("BTC/USD", 1, Timestamp::from_secs(1)));
+    let rate_limit = RateLimitConfig::new(RateLimit::per_second(100));
+    let client = PragmaClient::new(server.config(API_KEY).with_rate_limit(rate_limit)).unwrap();
+
+    // The retry, held for `Retry-After`, succeeds once the quota is available again.
+    server.inject_fault(Fault::RateLimited(Duration::from_millis(200)));
+    let request = tokio::spawn({
+        let client = client.clone();
+        async move { client.get_entry("BTC", "USD", None).await }
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    server.clear_faults();
+    assert!(request.await.unwrap().is_ok());
+    assert_eq!(server.requests().len(), 2);
+
+    // A request rejected twice fails.
+    server.inject_fault(Fault::RateLimited(Duration::from_millis(50)));
+    assert!(matches!(
+        client.get_entry("BTC", "USD", None).await,
+        Err(PragmaError::RateLimited { .. })
+    ));
+    assert_eq!(server.requests().len(), 4);
+}
+
+#[tokio::test]
+async fn stale_prices_are_rejected() {
+    let server = MockServer::start().await.unwrap();
+    let clock = ManualClock::new(Timestamp::from_secs(1_000));
+    let client = PragmaClient::new(server.config(API_KEY).with_clock(clock.clone())).unwrap();
+    server.set_entry("BTC", "USD", entry("BTC/USD", 1, Timestamp::from_secs(990)));
+
+    let params = GetEntryParams {
+        max_age: Some(Duration::from_secs(30)),
+        ..Default::default()
+    };
+    let fresh = client.get_entry("BTC", "USD", Some(params.clone())).await;
+    assert!(fresh.is_ok());
+
+    clock.advance(Duration::from_secs(60));
+    let stale = client.get_entry("BTC", "USD", Some(params)).await;
+    let Err(PragmaError::StalePrice { age, max_age }) = stale else {
+        panic!("expected a stale price, got {stale:?}");
+    };
+    assert_eq!(age, Duration::from_secs(70));
+    assert_eq!(max_age, Duration::from_secs(30));
+    assert!(client.get_entry("BTC", "USD", None).await.is_ok());
+
+    let mut ws_client = client
+        .lightspeed_ws_client()
+        .with_max_age(Duration::from_secs(30));
+    ws_client.connect().await.unwrap();
+    for timestamp in [Timestamp::from_secs(1_050), Timestamp::from_secs(1_000)] {
+        server.push_lightspeed(&LightspeedMessage::PriceUpdate {
+            oracle_prices: vec![],
+            timestamp,
+        });
+    }
+    let fresh = tokio::time::timeout(Duration::from_secs(5), ws_client.recv_fresh()).await;
+    assert!(matches!(fresh, Ok(Some(Ok(_)))));
+    let stale = tokio::time::timeout(Duration::from_secs(5), ws_client.recv_fresh()).await;
+    assert!(matches!(
+        stale,
+        Ok(Some(Err(PragmaError::StalePrice { .. })))
+    ));
+}
+
+#[tokio::test]
+async fn deviation_monitor_thresholds() {
+    let (server, client) = setup().await;
+    // 100.00 offchain with 8 decimals, 100.60 onchain with 18 decimals: 60 bps.
+    server.set_entry(
+        "BTC",
+        "USD",
+        entry("BTC/USD", 10_000_000_000, Timestamp::from_secs(1_000)),
+    );
+    server.set_onchain_entry(
+        "BTC",
+        "USD",
+        GetOnchainEntryResponse {
+            asset_type: "Crypto".to_string(),
+            decimals: 18,
+            last_updated_timestamp: Timestamp::from_secs(700),
+            nb_sources_aggregated: 3,
+            pair_id: "BTC/USD".to_string(),
+            price: format!("{:#x}", 100_600_000_000_000_000_000u128),
+            components: None,
+        },
+    );
+
+    let monitor = DeviationMonitor::new(client, StarknetNetwork::Mainnet)
+        .pair("BTC", "USD")
+        .pair("ETH", "USD")
+        .max_deviation_bps(50.0)
+        .max_lag(Duration::from_secs(600));
+
+    let deviation = monitor.check("BTC", "USD").await.unwrap();
+    assert!((deviation.deviation_bps - 60.0).abs() < 1e-6);
+    assert_eq!(deviation.lag, Duration::from_secs(300));
+
+    let events = monitor
+        .clone()
+        .max_lag(Duration::from_secs(60))
+        .check_all()
+        .await;
+    assert!(matches!(
+        events.as_slice(),
+        [
+            DeviationEvent::PriceDeviation(_),
+            DeviationEvent::TimestampLag(_),
+            DeviationEvent::Error { pair, .. },
+        ] if pair == "ETH/USD"
+    ));
+
+    let mut events = monitor.interval(Duration::from_millis(50)).unwrap().start();
+    let event = tokio::time::timeout(Duration::from_secs(5), events.recv()).await;
+    assert!(matches!(event, Ok(Some(DeviationEvent::PriceDeviation(_)))));
+}
+
+#[tokio::test]
+async fn deviation_monitor_rejects_zero_interval() {
+    let (_, client) = setup().await;
+    let monitor = DeviationMonitor::new(client, StarknetNetwork::Mainnet).interval(Duration::ZERO);
+    assert!(matches!(monitor, Err(PragmaError::InvalidConfig(_))));
+}
+
+#[tokio::test]
+async fn rolling_windows_over_lightspeed() {
+    let server = MockServer::start().await.unwrap();
+    let clock = ManualClock::new(Timestamp::from_secs(1_010));
+    let client = PragmaClient::new(server.config(API_KEY).with_clock(clock.clone())).unwrap();
+    let mut ws_client = client.lightspeed_ws_client();
+    ws_client.connect().await.unwrap();
+    let mut stream = ws_client.rolling(RollingConfig {
+        window: Duration::from_secs(60),
+        ema_half_life: Duration::from_secs(10),
+        max_gap: Duration::from_secs(5),
+    });
+
+    // Two updates a second apart, then a gap of 10 seconds.
+    for (secs, price) in [(1_000, 100u128), (1_001, 110), (1_011, 120)] {
+        server.push_lightspeed(&LightspeedMessage::PriceUpdate {
+            oracle_prices: vec![PriceUpdate {
+                num_sources_aggregated: 3,
+                pair_id: "BTC/USD".to_string(),
+                price: format!("{price:#x}"),
+            }],
+            timestamp: Timestamp::from_secs(secs),
+        });
+        let update = tokio::time::timeout(Duration::from_secs(5), stream.recv()).await;
+        assert!(matches!(update, Ok(Some(_))));
+
+        if secs == 1_001 {
+            clock.set(Timestamp::from_secs(1_002));
+            let stats = stream.stats("BTC/USD").unwrap();
+            assert_eq!(stats.samples, 2);
+            assert_eq!(stats.twap, 105.0);
+            assert_eq!((stats.min, stats.max), (100.0, 110.0));
+            assert!(stats.ema > 100.0 && stats.ema < 110.0);
+            assert!(stats.volatility.unwrap() > 0.0);
+
+            clock.set(Timestamp::from_secs(1_010));
+            assert!(stream.stats("BTC/USD").unwrap().stale);
+        }
+    }
+
+    clock.set(Timestamp::from_secs(1_011));
+    let stats = stream.stats("BTC/USD").unwrap();
+    assert_eq!(stats.gaps, 1);
+    assert_eq!(stats.samples, 1);
+    assert_eq!(stats.ema, 120.0);
+    assert!(!stats.stale);
+    assert_eq!(stats.volatility, None);
+}
+
+#[tokio::test]
+async fn price_book_falls_back_to_http() {
+    let server = MockServer::start().await.unwrap();
+    let clock = ManualClock::new(Timestamp::from_secs(1_000));
+    let client = PragmaClient::new(server.config(API_KEY).with_clock(clock.clone())).unwrap();
+    server.set_entry(
+        "BTC",
+        "USD",
+        entry("BTC/USD", 100, Timestamp::from_secs(990)),
+    );
+
+    let book = PriceBook::lightspeed(&client, vec!["BTC/USD".to_string()])
+        .await
+        .unwrap()
+        .with_max_age(Duration::from_secs(5));
+
+    // Nothing received yet.
+    let price = book.get("BTC/USD").await.unwrap();
+    assert_eq!(price.origin, PriceOrigin::Http);
+    assert_eq!(price.price, "0x64");
+    assert!(matches!(
+        book.get("ETH-USD").await,
+        Err(PragmaError::PriceUnavailable(_))
+    ));
+
+    let mut updates = book.watch("BTC/USD");
+    server.push_lightspeed(&LightspeedMessage::PriceUpdate {
+        oracle_prices: vec![PriceUpdate {
+            num_sources_aggregated: 3,
+            pair_id: "BTC/USD".to_string(),
+            price: "0x6e".to_string(),
+        }],
+        timestamp: Timestamp::from_secs(1_000),
+    });
+    tokio::time::timeout(Duration::from_secs(5), updates.changed())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(updates.borrow().as_ref().unwrap().price, "0x6e");
+
+    let price = book.get("BTC/USD").await.unwrap();
+    assert_eq!(price.origin, PriceOrigin::WebSocket);
+    assert_eq!(price.price, "0x6e");
+    assert_eq!(book.pairs(), vec!["BTC/USD".to_string()]);
+
+    // The WebSocket price is now stale.
+    clock.set(Timestamp::from_secs(1_010));
+    let price = book.get("BTC/USD").await.unwrap();
+    assert_eq!(price.origin, PriceOrigin::Http);
+    assert_eq!(
+        book.latest("BTC/USD").unwrap().origin,
+        PriceOrigin::WebSocket
+    );
+}
+
+#[tokio::test]
+async fn starkex_price_book_stores_prices_by_pair() {
+    let (server, client) = setup().await;
+    let book = PriceBook::starkex(&client, vec!["BTC/USD".to_string()])
+        .await
+        .unwrap();
+
+    let mut updates = book.watch("BTC/USD");
+    let update = |global_asset_id: &str, median_price: &str| StarkexPriceUpdate {
+        global_asset_id: global_asset_id.to_string(),
+        median_price: median_price.to_string(),
+        signature: "0x0".to_string(),
+        signed_prices: vec![],
+    };
+    server.push_starkex(&StarkexMessage::PriceUpdate {
+        // "BTC-USD-8" and an id not decoding to a pair.
+        oracle_prices: vec![update("0x4254432d5553442d38", "100"), update("0x01", "7")],
+        timestamp: Timestamp::now(),
+    });
+    tokio::time::timeout(Duration::from_secs(5), updates.changed())
+        .await
+        .unwrap()
+        .unwrap();
+
+    let price = book.get("BTC/USD").await.unwrap();
+    assert_eq!(price.origin, PriceOrigin::WebSocket);
+    assert_eq!(price.price, "100");
+    assert_eq!(book.latest("0x01").unwrap().price, "7");
+}
+
+/// Waits for the WebSocket frames received by the server to satisfy `done`.
+async fn wait_for_ws_messages(
+    server: &MockServer,
+    done: impl Fn(&[String]) -> bool,
+) -> Vec<String> {
+    for _ in 0..100 {
+        let messages = server.ws_messages();
+        if done(&messages) {
+            return messages;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("unexpected frames: {:?}", server.ws_messages());
+}
+
+#[tokio::test]
+async fn subscription_hub_shares_one_connection() {
+    let (server, client) = setup().await;
+    let mut ws_client = client.lightspeed_ws_client();
+    ws_client.connect().await.unwrap();
+    let hub = ws_client.hub(DEFAULT_HUB_CAPACITY);
+
+    let mut all = hub
+        .subscribe(vec!["BTC/USD".to_string(), "ETH/USD".to_string()])
+        .unwrap();
+    let mut btc = hub.subscribe(vec!["BTC/USD".to_string()]).unwrap();
+    assert_eq!(hub.subscriptions()["BTC/USD"], 2);
+    assert_eq!(server.ws_connection_count(), 1);
+    // BTC/USD is only subscribed to once upstream.
+    wait_for_ws_messages(&server, |messages| messages.len() == 1).await;
+
+    let update = |pair: &str| PriceUpdate {
+        num_sources_aggregated: 3,
+        pair_id: pair.to_string(),
+        price: "0x64".to_string(),
+    };
+    server.push_lightspeed(&LightspeedMessage::PriceUpdate {
+        oracle_prices: vec![update("BTC/USD"), update("ETH/USD")],
+        timestamp: Timestamp::from_secs(1_000),
+    });
+    let pairs = |msg| match msg {
+        Some(Ok(LightspeedMessage::PriceUpdate { oracle_prices, .. })) => oracle_prices
+            .into_iter()
+            .map(|u| u.pair_id)
+            .collect::<Vec<_>>(),
+        msg => panic!("unexpected message: {msg:?}"),
+    };
+    let timeout = Duration::from_secs(5);
+    let received = tokio::time::timeout(timeout, all.recv()).await.unwrap();
+    assert_eq!(pairs(received), ["BTC/USD", "ETH/USD"]);
+    let received = tokio::time::timeout(timeout, btc.recv()).await.unwrap();
+    assert_eq!(pairs(received), ["BTC/USD"]);
+
+    drop(all);
+    let messages = wait_for_ws_messages(&server, |messages| messages.len() == 2).await;
+    assert!(messages[1].contains("unsubscribe") && messages[1].contains("ETH/USD"));
+    assert!(!messages[1].contains("BTC/USD"));
+
+    drop(btc);
+    let messages = wait_for_ws_messages(&server, |messages| messages.len() == 3).await;
+    assert!(messages[2].contains("unsubscribe") && messages[2].contains("BTC/USD"));
+    assert!(hub.subscriptions().is_empty());
+
+    // The subscriptions end with the connection, even while the hub is alive.
+    let mut eth = hub.subscribe(vec!["ETH/USD".to_string()]).unwrap();
+    server.drop_ws_connections();
+    let received = tokio::time::timeout(timeout, eth.recv()).await.unwrap();
+    assert!(received.is_none());
+}
+
+#[cfg(feature = "sync")]
+#[tokio::test(flavor = "multi_thread")]
+async fn blocking_client_inside_and_outside_a_runtime() {
+    use pragma_rs::BlockingPragmaClient;
+
+    let (server, client) = setup().await;
+    server.set_entry("BTC", "USD", entry("BTC/USD", 42, Timestamp::from_secs(1)));
+
+    // Called from an async context, where `Runtime::block_on` would panic.
+    let blocking = BlockingPragmaClient::from_client(client.clone()).unwrap();
+    assert_eq!(
+        blocking.get_entry("BTC", "USD", None).unwrap().price,
+        "0x2a"
+    );
+    assert!(blocking.is_healthy());
+    assert_eq!(
+        client.get_entry_sync("BTC", "USD", None).unwrap().price,
+        "0x2a"
+    );
+
+    // Called from a thread without runtime, and dropped there.
+    let price = std::thread::spawn(move || blocking.get_entry("BTC", "USD", None).unwrap().price)
+        .join()
+        .unwrap();
+    assert_eq!(price, "0x2a");
+}
+
+#[cfg(feature = "sync")]
+#[test]
+fn blocking_client_outlives_the_runtime_of_its_client() {
+    use pragma_rs::BlockingPragmaClient;
+
+    let server_runtime = tokio::runtime::Runtime::new().unwrap();
+    let (server, client) = server_runtime.block_on(setup());
+    server.set_entry("BTC", "USD", entry("BTC/USD", 42, Timestamp::from_secs(1)));
+
+    // The async client pools a connection driven by the runtime of the caller.
+    let caller_runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    caller_runtime
+        .block_on(client.get_entry("BTC", "USD", None))
+        .unwrap();
+    let blocking = BlockingPragmaClient::from_client(client).unwrap();
+    drop(caller_runtime);
+
+    assert_eq!(
+        blocking.get_entry("BTC", "USD", None).unwrap().price,
+        "0x2a"
+    );
+}
+
+#[cfg(feature = "sync")]
+#[tokio::test(flavor = "multi_thread")]
+async fn blocking_ws_client() {
+    use std::sync::mpsc::RecvTimeoutError;
+
+    use pragma_rs::BlockingPragmaClient;
+
+    let (server, client) = setup().await;
+    let blocking = BlockingPragmaClient::from_client(client).unwrap();
+    let mut ws_client = blocking.lightspeed_ws_client();
+    ws_client.connect().unwrap();
+    ws_client.subscribe(vec!["BTC/USD".to_string()]).unwrap();
+    let ack = ws_client.recv_timeout(Duration::from_secs(5));
+    assert!(matches!(ack, Ok(LightspeedMessage::Subscribe { .. })));
+
+    let timeout = Duration::from_millis(50);
+    assert_eq!(
+        ws_client.recv_timeout(timeout).unwrap_err(),
+        RecvTimeoutError::Timeout
+    );
+
+    for secs in [1, 2] {
+        server.push_lightspeed(&LightspeedMessage::PriceUpdate {
+            oracle_prices: vec![PriceUpdate {
+                num_sources_aggregated: 3,
+                pair_id: "BTC/USD".to_string(),
+                price: "0x64".to_string(),
+            }],
+            timestamp: Timestamp::from_secs(secs),
+        });
+    }
+    let msg = ws_client.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert!(
+        matches!(msg, LightspeedMessage::PriceUpdate { timestamp, .. } if timestamp == Timestamp::from_secs(1))
+    );
+    let msg = ws_client.next().unwrap();
+    assert!(
+        matches!(msg, LightspeedMessage::PriceUpdate { timestamp, .. } if timestamp == Timestamp::from_secs(2))
+    );
+
+    // Dropping the client closes the connection.
+    drop(ws_client);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(server.ws_connection_count(), 0);
+
+    // The iterator ends when the server closes the connection.
+    let mut ws_client = blocking.lightspeed_ws_client();
+    ws_client.connect().unwrap();
+    server.drop_ws_connections();
+    assert_eq!(
+        ws_client.recv_timeout(Duration::from_secs(5)).unwrap_err(),
+        RecvTimeoutError::Disconnected
+    );
+    assert!(ws_client.next().is_none());
+}
+
+#[tokio::test]
+async fn api_key_rotation() {
+    let server = MockServer::start().await.unwrap();
+    server.require_api_key("first_key");
+    server.set_entry("BTC", "USD", entry("BTC/USD", 42, Timestamp::from_secs(1)));
+    let key = RotatingApiKey::new("first_key");
+    let config = server.config("unused").with_api_key_provider(key.clone());
+    assert!(!format!("{config:?}").contains("first_key"));
+    let client = PragmaClient::new(config).unwrap();
+    assert!(!format!("{client:?}").contains("first_key"));
+
+    client.get_entry("BTC", "USD", None).await.unwrap();
+
+    key.rotate("second_key");
+    assert!(matches!(
+        client.get_entry("BTC", "USD", None).await,
+        Err(PragmaError::Unauthorized(_))
+    ));
+    server.require_api_key("second_key");
+    client.get_entry("BTC", "USD", None).await.unwrap();
+
+    let mut ws_client = client.lightspeed_ws_client();
+    ws_client.connect().await.unwrap();
+    let requests = server.requests();
+    assert_eq!(
+        requests.last().unwrap().api_key.as_deref(),
+        Some("second_key")
+    );
+}
+
+#[tokio::test]
+async fn failover_between_endpoints() {
+    let primary = MockServer::start().await.unwrap();
+    let fallback = MockServer::start().await.unwrap();
+    for server in [&primary, &fallback] {
+        server.set_entry("BTC", "USD", entry("BTC/USD", 1, Timestamp::from_secs(1)));
+    }
+    let failover = FailoverConfig {
+        min_requests: 2,
+        window: 4,
+        probe_interval: Duration::ZERO,
+        failback_after: Duration::from_millis(300),
+        ..FailoverConfig::default()
+    };
+    let client = PragmaClient::new(
+        primary
+            .config(API_KEY)
+            .with_fallback(fallback.environment())
+            .with_failover(failover),
+    )
+    .unwrap();
+
+    // Two failed requests out of two leave the primary endpoint.
+    primary.inject_fault(Fault::Status(503));
+    assert!(client.get_entry("BTC", "USD", None).await.is_err());
+    assert!(client.get_entry("BTC", "USD", None).await.is_err());
+    assert!(client.get_entry("BTC", "USD", None).await.is_ok());
+    assert_eq!(fallback.requests().len(), 1);
+
+    // The recovered primary endpoint only gets the traffic back once it stayed healthy.
+    primary.clear_faults();
+    client.get_entry("BTC", "USD", None).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    client.get_entry("BTC", "USD", None).await.unwrap();
+    assert_eq!(fallback.requests().len(), 3);
+    tokio::time::sleep(Duration::from_millis(350)).await;
+    client.get_entry("BTC", "USD", None).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let served = primary.requests().len();
+    client.get_entry("BTC", "USD", None).await.unwrap();
+    assert_eq!(primary.requests().len(), served + 1);
+    assert_eq!(fallback.requests().len(), 4);
+}
+
+#[tokio::test]
+async fn zero_error_rate_only_fails_over_on_errors() {
+    let primary = MockServer::start().await.unwrap();
+    let fallback = MockServer::start().await.unwrap();
+    for server in [&primary, &fallback] {
+        server.set_entry("BTC", "USD", entry("BTC/USD", 1, Timestamp::from_secs(1)));
+    }
+    let failover = FailoverConfig {
+        max_error_rate: 0.0,
+        min_requests: 2,
+        ..FailoverConfig::default()
+    };
+    let client = PragmaClient::new(
+        primary
+            .config(API_KEY)
+            .with_fallback(fallback.environment())
+            .with_failover(failover),
+    )
+    .unwrap();
+
+    for _ in 0..5 {
+        client.get_entry("BTC", "USD", None).await.unwrap();
+    }
+    assert_eq!(fallback.requests().len(), 0);
+
+    // A single failure is enough.
+    primary.inject_fault(Fault::Status(503));
+    assert!(client.get_entry("BTC", "USD", None).await.is_err());
+    client.get_entry("BTC", "USD", None).await.unwrap();
+    assert_eq!(fallback.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn ws_connect_fails_over() {
+    let fallback = MockServer::start().await.unwrap();
+    fallback.set_entry("BTC", "USD", entry("BTC/USD", 1, Timestamp::from_secs(1)));
+    // Nothing listens on port 1.
+    let unreachable = Environment::Local {
+        http_base_url: "http://127.0.0.1:1".to_string(),
+        ws_base_url: "ws://127.0.0.1:1".to_string(),
+    };
+    let config = Config::new(API_KEY, unreachable).with_fallback(fallback.environment());
+    let client = PragmaClient::new(config).unwrap();
+
+    let mut ws_client = client.lightspeed_ws_client();
+    ws_client.connect().await.unwrap();
+    assert_eq!(fallback.ws_connection_count(), 1);
+
+    // HTTP requests follow the WebSocket to the fallback endpoint.
+    client.get_entry("BTC", "USD", None).await.unwrap();
+}
+
+#[tokio::test]
+async fn ws_auth_failure_does_not_fail_over() {
+    let primary = MockServer::start().await.unwrap();
+    let fallback = MockServer::start().await.unwrap();
+    primary.require_api_key("rotated_key");
+    let config = primary
+        .config(API_KEY)
+        .with_fallback(fallback.environment());
+    let client = PragmaClient::new(config).unwrap();
+
+    let mut ws_client = client.lightspeed_ws_client();
+    assert!(matches!(
+        ws_client.connect().await,
+        Err(WsError::Rejected(401))
+    ));
+    assert_eq!(fallback.ws_connection_count(), 0);
+
+    // A server error still fails over.
+    primary.require_api_key(API_KEY);
+    primary.inject_fault(Fault::Status(503));
+    let mut ws_client = client.lightspeed_ws_client();
+    ws_client.connect().await.unwrap();
+    assert_eq!(fallback.ws_connection_count(), 1);
+}
+
+/// Receives the next message of `ws_client`, and returns the price of its first update.
+async fn recv_price(ws_client: &mut PragmaWsClient<LightspeedMessage>) -> String {
+    let update = tokio::time::timeout(Duration::from_secs(5), ws_client.recv()).await;
+    match update {
+        Ok(Some(LightspeedMessage::PriceUpdate { oracle_prices, .. })) => {
+            oracle_prices[0].price.clone()
+        }
+        update => panic!("expected a price update, got {update:?}"),
+    }
+}
+
+#[tokio::test]
+async fn ws_reconnects_to_the_active_endpoint() {
+    let primary = MockServer::start().await.unwrap();
+    let fallback = MockServer::start().await.unwrap();
+    let failover = FailoverConfig {
+        probe_interval: Duration::ZERO,
+        failback_after: Duration::from_millis(300),
+        ..FailoverConfig::default()
+    };
+    let reconnect = ReconnectConfig {
+        initial_backoff: Duration::from_millis(10),
+        max_backoff: Duration::from_millis(50),
+        max_attempts: None,
+    };
+    let client = PragmaClient::new(
+        primary
+            .config(API_KEY)
+            .with_fallback(fallback.environment())
+            .with_failover(failover)
+            .with_ws_reconnect(reconnect),
+    )
+    .unwrap();
+
+    let mut ws_client = client.lightspeed_ws_client();
+    ws_client.connect().await.unwrap();
+    ws_client
+        .send(LightspeedMessage::subscribe(vec!["BTC/USD".to_string()]))
+        .unwrap();
+    let ack = tokio::time::timeout(Duration::from_secs(5), ws_client.recv()).await;
+    assert!(matches!(ack, Ok(Some(LightspeedMessage::Subscribe { .. }))));
+
+    let update = |price: &str| LightspeedMessage::PriceUpdate {
+        oracle_prices: vec![PriceUpdate {
+            num_sources_aggregated: 5,
+            pair_id: "BTC/USD".to_string(),
+            price: price.to_string(),
+        }],
+        timestamp: Timestamp::from_millis(1_746_000_000_123),
+    };
+    primary.push_lightspeed(&update("0x1"));
+    assert_eq!(recv_price(&mut ws_client).await, "0x1");
+
+    // The primary endpoint goes down while streaming: the client resubscribes on the fallback.
+    primary.inject_fault(Fault::Status(503));
+    primary.drop_ws_connections();
+    wait_for_ws_messages(&fallback, |frames| {
+        frames.iter().any(|frame| frame.contains("BTC/USD"))
+    })
+    .await;
+    let ack = tokio::time::timeout(Duration::from_secs(5), ws_client.recv()).await;
+    assert!(matches!(ack, Ok(Some(LightspeedMessage::Subscribe { .. }))));
+    fallback.push_lightspeed(&update("0x2"));
+    assert_eq!(recv_price(&mut ws_client).await, "0x2");
+
+    // Once the primary endpoint recovered, the connection fails back to it.
+    primary.clear_faults();
+    for _ in 0..100 {
+        if primary.ws_connection_count() == 1 && fallback.ws_connection_count() == 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(primary.ws_connection_count(), 1);
+    assert_eq!(fallback.ws_connection_count(), 0);
+    let ack = tokio::time::timeout(Duration::from_secs(5), ws_client.recv()).await;
+    assert!(matches!(ack, Ok(Some(LightspeedMessage::Subscribe { .. }))));
+    primary.push_lightspeed(&update("0x3"));
+    assert_eq!(recv_price(&mut ws_client).await, "0x3");
+}
+
+#[tokio::test]
+async fn health_reports_status_and_version() {
+    let (server, client) = setup().await;
+    server.set_version("1.2.3");
+
+    let health = client.health().await;
+    assert!(health.is_healthy());
+    assert_eq!(health.url, format!("{}/node", server.http_base_url()));
+    assert_eq!(health.status, Some(200));
+    assert_eq!(health.version.as_deref(), Some("1.2.3"));
+    assert_eq!(health.error, None);
+
+    server.inject_fault(Fault::Status(503));
+    let health = client.health().await;
+    assert!(health.reachable && !health.is_healthy());
+    assert_eq!(health.status, Some(503));
+    assert!(health.error.unwrap().contains("Injected fault"));
+
+    // Nothing listens on port 1.
+    let unreachable = Environment::Local {
+        http_base_url: "http://127.0.0.1:1".to_string(),
+        ws_base_url: "ws://127.0.0.1:1".to_string(),
+    };
+    let client = PragmaClient::new(Config::new(API_KEY, unreachable)).unwrap();
+    let health = client.health().await;
+    assert!(!health.reachable);
+    assert_eq!(health.status, None);
+    assert!(health.error.is_some());
+}
+
+#[tokio::test]
+async fn health_timeout_is_configurable() {
+    let (server, _) = setup().await;
+    server.inject_fault(Fault::Latency(Duration::from_millis(500)));
+    let config = server
+        .config(API_KEY)
+        .with_health_timeout(Duration::from_millis(100));
+    let client = PragmaClient::new(config).unwrap();
+
+    let health = client.health().await;
+    assert!(!health.reachable);
+    assert!(health.latency < Duration::from_millis(500));
+    assert!(!client.is_healthy().await);
+}
+
+#[tokio::test]
+async fn health_watcher_rejects_zero_interval() {
+    let (server, client) = setup().await;
+    let watcher = client.watch_health(Duration::ZERO).await;
+    assert!(matches!(watcher, Err(PragmaError::InvalidConfig(_))));
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test]
+async fn health_watcher_publishes_changes() {
+    let (server, client) = setup().await;
+    let mut watcher = client
+        .watch_health(Duration::from_millis(20))
+        .await
+        .unwrap();
+    assert!(watcher.current().is_healthy());
+
+    server.inject_fault(Fault::Status(503));
+    let health = tokio::time::timeout(Duration::from_secs(5), watcher.changed())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(health.status, Some(503));
+
+    server.clear_faults();
+    let health = tokio::time::timeout(Duration::from_secs(5), watcher.changed())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(health.is_healthy());
+
+    // Checks reporting the same state are not published.
+    let changed = tokio::time::timeout(Duration::from_millis(200), watcher.changed()).await;
+    assert!(changed.is_err());
+}